@@ -21,9 +21,135 @@ fn neighbor_count(matrix: &[Vec<i32>], x: usize, y: usize) -> usize {
     neighbor_count
 }
 
+/// Storage used for the roll grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// One `i32` per cell, neighbours counted cell by cell.
+    Matrix,
+    /// One bit per cell, neighbours counted 64 cells at a time.
+    Bitset,
+}
+
+/// Bit-packed roll grid: bit `c % 64` of word `c / 64` in a row is column `c`.
+pub struct BitGrid {
+    rows: usize,
+    words_per_row: usize,
+    bits: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn parse(input: &str) -> Self {
+        let lines: Vec<&str> = input
+            .lines()
+            .map(|l| l.trim_end_matches('\r'))
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        let rows = lines.len();
+        let cols = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        let words_per_row = cols.div_ceil(64);
+        let mut bits = vec![0u64; rows * words_per_row];
+
+        for (x, line) in lines.iter().enumerate() {
+            for (y, b) in line.bytes().enumerate() {
+                if b == b'@' {
+                    bits[x * words_per_row + y / 64] |= 1 << (y % 64);
+                }
+            }
+        }
+
+        BitGrid { rows, words_per_row, bits }
+    }
+
+    fn row(&self, x: usize) -> &[u64] {
+        &self.bits[x * self.words_per_row..(x + 1) * self.words_per_row]
+    }
+
+    /// Word `w` of row `x` together with its left/right shifted copies, so that
+    /// bit `c` of the result holds the cell at column `c`, `c - 1` and `c + 1`.
+    fn shifted(&self, x: usize, w: usize) -> [u64; 3] {
+        let row = self.row(x);
+        let cur = row[w];
+        let prev = if w > 0 { row[w - 1] } else { 0 };
+        let next = if w + 1 < row.len() { row[w + 1] } else { 0 };
+        [cur, (cur << 1) | (prev >> 63), (cur >> 1) | (next << 63)]
+    }
+
+    /// Mask of rolls in row `x` with fewer than four neighbouring rolls.
+    ///
+    /// The eight neighbour masks are summed with a bit-sliced counter, so every
+    /// word handles 64 cells at once with plain shifts, ands and xors.
+    pub fn accessible_row(&self, x: usize) -> Vec<u64> {
+        (0..self.words_per_row)
+            .map(|w| {
+                let [cur, left, right] = self.shifted(x, w);
+                let above = if x > 0 { self.shifted(x - 1, w) } else { [0; 3] };
+                let below = if x + 1 < self.rows { self.shifted(x + 1, w) } else { [0; 3] };
+                let neighbours = [left, right, above[0], above[1], above[2], below[0], below[1], below[2]];
+
+                // ones/twos hold the count modulo 4, at_least_four latches the carry out.
+                let (mut ones, mut twos, mut at_least_four) = (0u64, 0u64, 0u64);
+                for n in neighbours {
+                    let carry = ones & n;
+                    ones ^= n;
+                    at_least_four |= twos & carry;
+                    twos ^= carry;
+                }
+
+                cur & !at_least_four
+            })
+            .collect()
+    }
+
+    /// Number of rolls with fewer than four neighbouring rolls.
+    pub fn accessible_count(&self) -> u32 {
+        (0..self.rows)
+            .flat_map(|x| self.accessible_row(x))
+            .map(|w| w.count_ones())
+            .sum()
+    }
+
+    /// Repeatedly removes every accessible roll until none are left, returning
+    /// how many were removed in total.
+    pub fn remove_accessible(&mut self) -> u32 {
+        let mut count = 0u32;
+
+        loop {
+            let masks: Vec<Vec<u64>> = (0..self.rows).map(|x| self.accessible_row(x)).collect();
+            let removed: u32 = masks.iter().flatten().map(|w| w.count_ones()).sum();
+
+            if removed == 0 {
+                break;
+            }
+
+            for (x, mask) in masks.iter().enumerate() {
+                let start = x * self.words_per_row;
+                for (word, m) in self.bits[start..start + self.words_per_row].iter_mut().zip(mask) {
+                    *word &= !m;
+                }
+            }
+
+            count += removed;
+        }
+
+        count
+    }
+}
+
 fn silver_star(input: Option<&str>) -> u32 {
+    silver_star_with(input, Backend::Matrix)
+}
+
+pub fn silver_star_with(input: Option<&str>, backend: Backend) -> u32 {
     let input = input.unwrap_or(include_str!("../input/day4.txt"));
 
+    match backend {
+        Backend::Matrix => silver_matrix(input),
+        Backend::Bitset => BitGrid::parse(input).accessible_count(),
+    }
+}
+
+fn silver_matrix(input: &str) -> u32 {
     let matrix: Vec<Vec<i32>> = input
         .lines()
         .filter(|l| !l.is_empty())
@@ -53,8 +179,19 @@ fn silver_star(input: Option<&str>) -> u32 {
 }
 
 fn gold_star(input: Option<&str>) -> u32 {
- let input = input.unwrap_or(include_str!("../input/day4.txt"));
+    gold_star_with(input, Backend::Matrix)
+}
 
+pub fn gold_star_with(input: Option<&str>, backend: Backend) -> u32 {
+    let input = input.unwrap_or(include_str!("../input/day4.txt"));
+
+    match backend {
+        Backend::Matrix => gold_matrix(input),
+        Backend::Bitset => BitGrid::parse(input).remove_accessible(),
+    }
+}
+
+fn gold_matrix(input: &str) -> u32 {
     let mut matrix: Vec<Vec<i32>> = input
         .lines()
         .filter(|l| !l.is_empty())
@@ -127,4 +264,39 @@ mod tests {
         assert_eq!(gold_star(Some(TEST_INPUT)), 43);
         println!("{}", gold_star(None));
     }   
+
+    #[test]
+    fn test_bitset(){
+        assert_eq!(silver_star_with(Some(TEST_INPUT), Backend::Bitset), 13);
+        assert_eq!(gold_star_with(Some(TEST_INPUT), Backend::Bitset), 43);
+        assert_eq!(silver_star_with(None, Backend::Bitset), silver_star(None));
+        assert_eq!(gold_star_with(None, Backend::Bitset), gold_star(None));
+    }
+
+    /// Pseudo-random grid large enough to span many words per row.
+    fn synthetic_grid(rows: usize, cols: usize) -> String {
+        let mut rng = crate::TestRng::new(0x2545_f491_4f6c_dd1d);
+        let mut out = String::with_capacity(rows * (cols + 1));
+        for _ in 0..rows {
+            for _ in 0..cols {
+                out.push(if rng.below(3) == 0 { '.' } else { '@' });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_backends(){
+        use std::time::Instant;
+
+        let grid = synthetic_grid(1000, 1000);
+        for backend in [Backend::Matrix, Backend::Bitset] {
+            let start = Instant::now();
+            let silver = silver_star_with(Some(&grid), backend);
+            let gold = gold_star_with(Some(&grid), backend);
+            println!("{:?}: silver={} gold={} in {:?}", backend, silver, gold, start.elapsed());
+        }
+    }
 }
\ No newline at end of file
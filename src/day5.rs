@@ -1,74 +1,491 @@
-fn silver_star(input: Option<&str>) -> u32 {
-    let input = input
-        .unwrap_or(include_str!("../input/day5.txt"))
-        .replace("\r\n", "\n");
+use std::fmt;
+use std::io::{self, BufRead};
 
+/// A set of `u64` values stored as sorted, disjoint, non-adjacent inclusive ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<(u64, u64)>,
+}
 
-    let parts: Vec<&str> = input.split("\n\n").collect();
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    let ranges: Vec<(u64, u64)> = parts[0]
-        .lines()
-        .map(|line| {
-            let (start, end) = line.split_once('-').unwrap();
-            (start.parse().unwrap(), end.parse().unwrap())
-        })
-        .collect();
+    /// The merged ranges in increasing order.
+    pub fn ranges(&self) -> &[(u64, u64)] {
+        &self.ranges
+    }
 
-    let mut count = 0;
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
 
-    for product in parts[1].lines() {
-        let n: u64 = product.parse().unwrap();
-        if ranges.iter().any(|&(s, e)| n >= s && n <= e) {
-            count += 1;
+    /// Adds every value in `start..=end`, merging with overlapping or adjacent ranges.
+    pub fn insert(&mut self, start: u64, end: u64) {
+        if start > end {
+            return;
+        }
+
+        // Ranges in lo..hi overlap or touch the new one
+        let lo = self.ranges.partition_point(|&(_, e)| e.saturating_add(1) < start);
+        let hi = self.ranges.partition_point(|&(s, _)| s <= end.saturating_add(1));
+
+        let (mut new_start, mut new_end) = (start, end);
+        if lo < hi {
+            new_start = new_start.min(self.ranges[lo].0);
+            new_end = new_end.max(self.ranges[hi - 1].1);
         }
+
+        self.ranges.splice(lo..hi, [(new_start, new_end)]);
     }
 
-    count
+    /// Removes every value in `start..=end`, splitting ranges that straddle it.
+    pub fn remove(&mut self, start: u64, end: u64) {
+        if start > end {
+            return;
+        }
+
+        // Ranges in lo..hi overlap the removed one
+        let lo = self.ranges.partition_point(|&(_, e)| e < start);
+        let hi = self.ranges.partition_point(|&(s, _)| s <= end);
+        if lo == hi {
+            return;
+        }
+
+        let mut leftovers = Vec::with_capacity(2);
+        let (first_start, _) = self.ranges[lo];
+        let (_, last_end) = self.ranges[hi - 1];
+        if first_start < start {
+            leftovers.push((first_start, start - 1));
+        }
+        if last_end > end {
+            leftovers.push((end + 1, last_end));
+        }
+
+        self.ranges.splice(lo..hi, leftovers);
+    }
+
+    /// Membership test in O(log n).
+    pub fn contains(&self, value: u64) -> bool {
+        let i = self.ranges.partition_point(|&(s, _)| s <= value);
+        i > 0 && self.ranges[i - 1].1 >= value
+    }
+
+    /// Number of values in the set, saturating at `u64::MAX`.
+    pub fn total_len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .fold(0u64, |acc, &(s, e)| acc.saturating_add((e - s).saturating_add(1)))
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.ranges.iter().chain(&other.ranges).copied().collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a_start, a_end) = self.ranges[i];
+            let (b_start, b_end) = other.ranges[j];
+
+            let start = a_start.max(b_start);
+            let end = a_end.min(b_end);
+            if start <= end {
+                ranges.push((start, end));
+            }
+
+            // Advance whichever range finishes first
+            if a_end < b_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        self.intersection(&other.complement(0, u64::MAX))
+    }
+
+    /// Every value in `lo..=hi` that is not in the set.
+    pub fn complement(&self, lo: u64, hi: u64) -> IntervalSet {
+        let mut ranges = Vec::new();
+        if lo > hi {
+            return IntervalSet { ranges };
+        }
+
+        let mut next = Some(lo);
+        for &(s, e) in &self.ranges {
+            let Some(from) = next else { break };
+            if s > hi {
+                break;
+            }
+            if s > from {
+                ranges.push((from, s - 1));
+            }
+            if e >= from {
+                next = e.checked_add(1);
+            }
+        }
+
+        if let Some(from) = next.filter(|&from| from <= hi) {
+            ranges.push((from, hi));
+        }
+
+        IntervalSet { ranges }
+    }
 }
 
-fn gold_star(input: Option<&str>) -> u64 {
-    let normalized_input = input
-        .unwrap_or(include_str!("../input/day5.txt"))
-        .replace("\r\n", "\n");
-
-    let sections: Vec<&str> = normalized_input.split("\n\n").collect();
-
-    // ---- Parse the fresh ID ranges ----
-    let mut ranges: Vec<(u64, u64)> = sections[0]
-        .lines()
-        .map(|line| {
-            let (start_str, end_str) = line.split_once('-').unwrap();
-            let start_id = start_str.parse::<u64>().unwrap();
-            let end_id = end_str.parse::<u64>().unwrap();
-            (start_id, end_id)
-        })
-        .collect();
+impl FromIterator<(u64, u64)> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = (u64, u64)>>(iter: I) -> Self {
+        let mut ranges: Vec<(u64, u64)> = iter.into_iter().filter(|&(s, e)| s <= e).collect();
 
-    ranges.sort_unstable_by_key(|range| range.0);
+        ranges.sort_unstable_by_key(|range| range.0);
 
-    let mut merged_ranges: Vec<(u64, u64)> = Vec::new();
+        let mut merged_ranges: Vec<(u64, u64)> = Vec::new();
 
-    for (range_start, range_end) in ranges {
-        match merged_ranges.last_mut() {
-            Some((_s, merged_end)) => {
-                let overlaps = range_start <= *merged_end + 1;
-                if overlaps {
+        for (range_start, range_end) in ranges {
+            match merged_ranges.last_mut() {
+                Some((_s, merged_end)) if range_start <= merged_end.saturating_add(1) => {
                     // Extend the merged range to cover the new one
                     *merged_end = (*merged_end).max(range_end);
-                } else {
-                    merged_ranges.push((range_start, range_end));
                 }
-            }
-            None => {
-                merged_ranges.push((range_start, range_end));
+                _ => merged_ranges.push((range_start, range_end)),
             }
         }
+
+        IntervalSet { ranges: merged_ranges }
     }
+}
+
+/// A line that could not be parsed, with its 1-based line number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} {:?}", self.line, self.reason, self.text)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for io::Error {
+    fn from(e: ParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Parses a fresh range entry: `start-end`, open-ended `start-` (up to
+/// `u64::MAX`) or a single ID.
+fn parse_range(text: &str) -> Result<(u64, u64), &'static str> {
+    let bound = |s: &str| s.trim().parse::<u64>().map_err(|_| "invalid range bound");
+
+    let (start, end) = match text.split_once('-') {
+        Some((start, end)) if end.trim().is_empty() => (bound(start)?, u64::MAX),
+        Some((start, end)) => (bound(start)?, bound(end)?),
+        None => {
+            let id = bound(text)?;
+            (id, id)
+        }
+    };
+
+    if start > end {
+        return Err("range start is after its end");
+    }
+    Ok((start, end))
+}
+
+fn parse_id(text: &str) -> Result<u64, &'static str> {
+    text.parse().map_err(|_| "invalid ingredient ID")
+}
+
+enum Entry<'a> {
+    Skip,
+    Range(&'a str),
+    Id(&'a str),
+}
+
+/// Line-by-line section tracker shared by the buffered and streaming parsers.
+///
+/// `#` starts a comment. The first blank line after any range entry switches to
+/// the ID section; any further blank lines are ignored.
+#[derive(Default)]
+struct Sections {
+    seen_range: bool,
+    in_ids: bool,
+}
+
+impl Sections {
+    fn classify<'a>(&mut self, raw: &'a str) -> Entry<'a> {
+        if raw.trim().is_empty() {
+            self.in_ids |= self.seen_range;
+            return Entry::Skip;
+        }
+
+        let text = raw.split('#').next().unwrap_or_default().trim();
+        if text.is_empty() {
+            Entry::Skip
+        } else if self.in_ids {
+            Entry::Id(text)
+        } else {
+            self.seen_range = true;
+            Entry::Range(text)
+        }
+    }
+}
+
+/// Both sections of the puzzle input, keeping line numbers for reporting.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Inventory {
+    pub ranges: Vec<SourceRange>,
+    pub ids: Vec<(usize, u64)>,
+}
+
+impl Inventory {
+    pub fn fresh(&self) -> IntervalSet {
+        self.ranges.iter().map(|r| (r.start, r.end)).collect()
+    }
+}
+
+/// Parses the puzzle input, collecting every invalid line instead of stopping
+/// at the first one.
+pub fn parse_inventory(input: &str) -> Result<Inventory, Vec<ParseError>> {
+    let mut inventory = Inventory::default();
+    let mut errors = Vec::new();
+    let mut sections = Sections::default();
+
+    for (i, raw) in input.lines().enumerate() {
+        let line = i + 1;
+        let error = |text: &str, reason| ParseError { line, text: text.to_string(), reason };
+
+        match sections.classify(raw) {
+            Entry::Skip => {}
+            Entry::Range(text) => match parse_range(text) {
+                Ok((start, end)) => inventory.ranges.push(SourceRange { line, start, end }),
+                Err(reason) => errors.push(error(text, reason)),
+            },
+            Entry::Id(text) => match parse_id(text) {
+                Ok(id) => inventory.ids.push((line, id)),
+                Err(reason) => errors.push(error(text, reason)),
+            },
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(inventory)
+    } else {
+        Err(errors)
+    }
+}
+
+/// A fresh range as written in the input, with its 1-based line number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceRange {
+    pub line: usize,
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Every original range that contains the ID.
+    Fresh(Vec<SourceRange>),
+    /// The closest ranges ending below and starting above the ID, if any.
+    Spoiled {
+        below: Option<SourceRange>,
+        above: Option<SourceRange>,
+    },
+}
+
+/// Why an available ingredient ID is considered fresh or spoiled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub line: usize,
+    pub id: u64,
+    pub verdict: Verdict,
+}
+
+/// Explains every available ingredient ID in terms of the original fresh ranges.
+pub fn explain(input: Option<&str>) -> Result<Vec<Explanation>, Vec<ParseError>> {
+    let inventory = parse_inventory(input.unwrap_or(include_str!("../input/day5.txt")))?;
 
-    merged_ranges
+    let mut by_start = inventory.ranges.clone();
+    by_start.sort_unstable_by_key(|r| (r.start, r.line));
+    let mut by_end = inventory.ranges.clone();
+    by_end.sort_unstable_by_key(|r| (r.end, r.line));
+
+    let explanations = inventory
+        .ids
         .iter()
-        .map(|(start_id, end_id)| end_id - start_id + 1)
-        .sum()
+        .map(|&(line, id)| {
+            // Only ranges starting at or before the ID can contain it
+            let started = by_start.partition_point(|r| r.start <= id);
+            let mut covering: Vec<SourceRange> =
+                by_start[..started].iter().filter(|r| r.end >= id).copied().collect();
+
+            let verdict = if covering.is_empty() {
+                let ended = by_end.partition_point(|r| r.end < id);
+                Verdict::Spoiled {
+                    below: ended.checked_sub(1).map(|i| by_end[i]),
+                    above: by_start.get(started).copied(),
+                }
+            } else {
+                covering.sort_unstable_by_key(|r| r.line);
+                Verdict::Fresh(covering)
+            };
+
+            Explanation { line, id, verdict }
+        })
+        .collect();
+
+    Ok(explanations)
+}
+
+/// Human-readable report of [`explain`], one ID per line.
+pub fn render_explanations(explanations: &[Explanation]) -> String {
+    let describe = |r: &SourceRange| format!("{}-{} (line {})", r.start, r.end, r.line);
+
+    let mut out = String::new();
+    for e in explanations {
+        let detail = match &e.verdict {
+            Verdict::Fresh(ranges) => {
+                let ranges: Vec<String> = ranges.iter().map(describe).collect();
+                format!("fresh, in {}", ranges.join(", "))
+            }
+            Verdict::Spoiled { below, above } => {
+                let below = below.as_ref().map_or("none".to_string(), describe);
+                let above = above.as_ref().map_or("none".to_string(), describe);
+                format!("spoiled, nearest below {}, nearest above {}", below, above)
+            }
+        };
+        out.push_str(&format!("line {}: {} {}\n", e.line, e.id, detail));
+    }
+    out
+}
+
+/// Reads lines from `reader` into `buf`, yielding `Ok(false)` at end of input.
+fn next_line<R: BufRead>(reader: &mut R, buf: &mut String) -> io::Result<bool> {
+    buf.clear();
+    Ok(reader.read_line(buf)? > 0)
+}
+
+/// Streaming classifier: builds the merged fresh index from the first section,
+/// then yields `(id, is_fresh)` for each line of the second section without
+/// buffering the IDs.
+pub struct IdStream<R> {
+    reader: R,
+    fresh: IntervalSet,
+    sections: Sections,
+    line: usize,
+    buf: String,
+}
+
+impl<R: BufRead> IdStream<R> {
+    /// Consumes the range section (up to the first blank line after it) from `reader`.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut buf = String::new();
+        let mut line = 0;
+        let mut ranges = Vec::new();
+        let mut sections = Sections::default();
+
+        while !sections.in_ids && next_line(&mut reader, &mut buf)? {
+            line += 1;
+            match sections.classify(&buf) {
+                Entry::Range(text) => {
+                    let range = parse_range(text)
+                        .map_err(|reason| ParseError { line, text: text.to_string(), reason })?;
+                    ranges.push(range);
+                }
+                // Only reachable once `in_ids` is set, which ends the loop
+                Entry::Skip | Entry::Id(_) => {}
+            }
+        }
+
+        Ok(IdStream { reader, fresh: ranges.into_iter().collect(), sections, line, buf })
+    }
+
+    pub fn fresh(&self) -> &IntervalSet {
+        &self.fresh
+    }
+}
+
+impl<R: BufRead> Iterator for IdStream<R> {
+    type Item = io::Result<(u64, bool)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match next_line(&mut self.reader, &mut self.buf) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+            self.line += 1;
+
+            let Entry::Id(text) = self.sections.classify(&self.buf) else {
+                continue;
+            };
+
+            return Some(match parse_id(text) {
+                Ok(id) => Ok((id, self.fresh.contains(id))),
+                Err(reason) => {
+                    Err(ParseError { line: self.line, text: text.to_string(), reason }.into())
+                }
+            });
+        }
+    }
+}
+
+/// Fresh and spoiled ID counts from a streamed input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamCounts {
+    pub fresh: u64,
+    pub spoiled: u64,
+}
+
+/// Counts fresh and spoiled IDs from any `BufRead` in constant memory
+/// (apart from the range index).
+pub fn count_streaming<R: BufRead>(reader: R) -> io::Result<StreamCounts> {
+    let mut counts = StreamCounts::default();
+    for item in IdStream::new(reader)? {
+        if item?.1 {
+            counts.fresh += 1;
+        } else {
+            counts.spoiled += 1;
+        }
+    }
+    Ok(counts)
+}
+
+fn silver_star(input: Option<&str>) -> u32 {
+    let inventory = parse_inventory(input.unwrap_or(include_str!("../input/day5.txt"))).unwrap();
+
+    let fresh = inventory.fresh();
+
+    let mut count = 0;
+
+    for &(_line, n) in &inventory.ids {
+        if fresh.contains(n) {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+fn gold_star(input: Option<&str>) -> u64 {
+    let inventory = parse_inventory(input.unwrap_or(include_str!("../input/day5.txt"))).unwrap();
+
+    inventory.fresh().total_len()
 }
 
 
@@ -105,4 +522,109 @@ mod tests {
         assert_eq!(gold_star(Some(TEST_INPUT)), 14);
         println!("{}", gold_star(None));
     }   
+
+    #[test]
+    fn test_interval_set(){
+        let mut set: IntervalSet = [(3, 5), (10, 14), (16, 20), (12, 18)].into_iter().collect();
+        assert_eq!(set.ranges(), &[(3, 5), (10, 20)]);
+        assert!(set.contains(5) && set.contains(17) && !set.contains(9));
+
+        set.insert(6, 9);
+        assert_eq!(set.ranges(), &[(3, 20)]);
+
+        set.remove(8, 12);
+        assert_eq!(set.ranges(), &[(3, 7), (13, 20)]);
+        assert_eq!(set.total_len(), 13);
+
+        let other: IntervalSet = [(0, 4), (15, 30)].into_iter().collect();
+        assert_eq!(set.union(&other).ranges(), &[(0, 7), (13, 30)]);
+        assert_eq!(set.intersection(&other).ranges(), &[(3, 4), (15, 20)]);
+        assert_eq!(set.difference(&other).ranges(), &[(5, 7), (13, 14)]);
+        assert_eq!(set.complement(0, 25).ranges(), &[(0, 2), (8, 12), (21, 25)]);
+    }
+
+    #[test]
+    fn test_interval_set_bounds(){
+        let mut set = IntervalSet::new();
+        set.insert(u64::MAX - 1, u64::MAX);
+        set.insert(0, 0);
+        assert_eq!(set.complement(0, u64::MAX).ranges(), &[(1, u64::MAX - 2)]);
+
+        set.remove(0, u64::MAX);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_explain(){
+        let explanations = explain(Some(TEST_INPUT)).unwrap();
+        assert_eq!(explanations.len(), 6);
+
+        let range = |line, start, end| SourceRange { line, start, end };
+
+        assert_eq!(explanations[0].line, 6);
+        assert_eq!(explanations[0].verdict, Verdict::Spoiled { below: None, above: Some(range(1, 3, 5)) });
+        assert_eq!(explanations[1].verdict, Verdict::Fresh(vec![range(1, 3, 5)]));
+        assert_eq!(explanations[2].verdict, Verdict::Spoiled { below: Some(range(1, 3, 5)), above: Some(range(2, 10, 14)) });
+        assert_eq!(explanations[4].verdict, Verdict::Fresh(vec![range(3, 16, 20), range(4, 12, 18)]));
+        assert_eq!(explanations[5].verdict, Verdict::Spoiled { below: Some(range(3, 16, 20)), above: None });
+
+        let fresh = explanations.iter().filter(|e| matches!(e.verdict, Verdict::Fresh(_))).count();
+        assert_eq!(fresh, 3);
+
+        print!("{}", render_explanations(&explanations));
+    }
+
+    #[test]
+    fn test_streaming(){
+        let counts = count_streaming(TEST_INPUT.as_bytes()).unwrap();
+        assert_eq!(counts, StreamCounts { fresh: 3, spoiled: 3 });
+
+        let crlf = TEST_INPUT.replace('\n', "\r\n");
+        assert_eq!(count_streaming(crlf.as_bytes()).unwrap(), counts);
+
+        let real = count_streaming(include_str!("../input/day5.txt").as_bytes()).unwrap();
+        assert_eq!(real.fresh, silver_star(None) as u64);
+
+        let err = count_streaming("3-5\n\n1\nx\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().starts_with("line 4"));
+    }
+
+    const MESSY_INPUT: &str = indoc! {"
+    # fresh ranges
+    3-5   
+    10-14 # inclusive
+    16-
+    7
+
+
+    # available IDs
+    1
+    7
+
+    12
+    99999999999
+    "};
+
+    #[test]
+    fn test_tolerant_parsing(){
+        let inventory = parse_inventory(MESSY_INPUT).unwrap();
+        let bounds: Vec<(u64, u64)> = inventory.ranges.iter().map(|r| (r.start, r.end)).collect();
+        assert_eq!(bounds, vec![(3, 5), (10, 14), (16, u64::MAX), (7, 7)]);
+        assert_eq!(inventory.ranges[3].line, 5);
+
+        let ids: Vec<(usize, u64)> = inventory.ids.clone();
+        assert_eq!(ids, vec![(9, 1), (10, 7), (12, 12), (13, 99999999999)]);
+
+        assert_eq!(silver_star(Some(MESSY_INPUT)), 3);
+        assert_eq!(count_streaming(MESSY_INPUT.as_bytes()).unwrap(), StreamCounts { fresh: 3, spoiled: 1 });
+        assert_eq!(count_streaming(MESSY_INPUT.replace('\n', "\r\n").as_bytes()).unwrap().fresh, 3);
+    }
+
+    #[test]
+    fn test_invalid_lines(){
+        let errors = parse_inventory("3-5\n9-4\nx-\n\n1\n-2\n").unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3, 6]);
+        assert_eq!(errors[0].to_string(), "line 2: range start is after its end \"9-4\"");
+    }
 }
\ No newline at end of file
@@ -1,76 +1,402 @@
-fn silver_star(input: Option<&str>) -> u64 {
-    let normalized_input = input
-        .unwrap_or(include_str!("../input/day6.txt"))
-        .replace("\r\n", "\n");
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
 
-    let lines: Vec<&str> = normalized_input.lines().collect();
+use num_bigint::BigUint;
+use num_traits::{CheckedDiv, CheckedSub, One, ToPrimitive, Zero};
 
-    // Parse the grid of numbers
-    let numbers: Vec<Vec<u64>> = lines[..lines.len() - 1]
-        .iter()
-        .map(|line| {
-            line.split_whitespace()
-                .map(|num_str| num_str.parse::<u64>().unwrap())
-                .collect()
-        })
-        .collect();
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProblemError {
+    UnknownOperator(String),
+    Overflow,
+    DivisionByZero,
+    NoOperands,
+}
 
-    lines[lines.len() - 1]
-        .split_whitespace()
-        .enumerate()
-        .map(|(i, op)| match op {
-            "*" => numbers.iter().map(|row| row[i]).product::<u64>(),
-            "+" => numbers.iter().map(|row| row[i]).sum::<u64>(),
-            _ => panic!("Unknown operation: {}", op),
-        })
-        .sum()
+impl fmt::Display for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProblemError::UnknownOperator(op) => write!(f, "unknown operator {:?}", op),
+            ProblemError::Overflow => write!(f, "arithmetic overflow"),
+            ProblemError::DivisionByZero => write!(f, "division by zero"),
+            ProblemError::NoOperands => write!(f, "problem has no operands"),
+        }
+    }
 }
 
-pub fn gold_star(input: Option<&str>) -> u64 {
-    let normalized_input = input
-        .unwrap_or(include_str!("../input/day6.txt"))
-        .replace("\r\n", "\n");
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorksheetError {
+    /// Problem `index` (0-based, left to right) could not be evaluated.
+    Problem { index: usize, error: ProblemError },
+    /// Every problem evaluated but their sum does not fit the number type.
+    TotalOverflow,
+    /// Problem `index` has no operator, or more than one, under its columns.
+    MissingOperator { index: usize },
+    /// An operand of problem `index` does not parse as the number type.
+    InvalidOperand { index: usize, text: String },
+}
+
+impl fmt::Display for WorksheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorksheetError::Problem { index, error } => write!(f, "problem {}: {}", index, error),
+            WorksheetError::TotalOverflow => write!(f, "grand total overflows"),
+            WorksheetError::MissingOperator { index } => {
+                write!(f, "problem {}: expected exactly one operator", index)
+            }
+            WorksheetError::InvalidOperand { index, text } => {
+                write!(f, "problem {}: invalid operand {:?}", index, text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WorksheetError {}
 
-    let lines: Vec<&str> = normalized_input.lines().collect();
+/// Operand and result type of a worksheet.
+///
+/// Implemented for `u64` and `u128` with overflow reported as an error, and for
+/// `BigUint` where only subtraction below zero can fail.
+pub trait Number: Clone + Ord + fmt::Debug + fmt::Display + FromStr + 'static {
+    fn zero() -> Self;
+    fn try_add(&self, other: &Self) -> Option<Self>;
+    fn try_sub(&self, other: &Self) -> Option<Self>;
+    fn try_mul(&self, other: &Self) -> Option<Self>;
+    /// `None` only on division by zero.
+    fn try_div(&self, other: &Self) -> Option<Self>;
+    /// `None` only on division by zero.
+    fn try_rem(&self, other: &Self) -> Option<Self>;
+    fn try_pow(&self, exp: &Self) -> Option<Self>;
+}
 
-    let n_rows = lines.len() - 1; // last line = operators
-    let n_cols = lines[0].len();
-    let ops_line: Vec<char> = lines[n_rows].chars().filter(|c| !c.is_whitespace()).collect();
+macro_rules! impl_number {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            fn zero() -> Self {
+                0
+            }
 
-    let mut num_cols: Vec<Vec<u64>> = Vec::new();
-    let mut current_col: Vec<u64> = Vec::new();
+            fn try_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
 
-    for i in 0..n_cols {
-        // Skip column if all spaces
-        if lines[..n_rows].iter().all(|line| line.chars().nth(i) == Some(' ')) {
-            if !current_col.is_empty() {
-                num_cols.push(current_col);
-                current_col = Vec::new();
+            fn try_sub(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *other)
+            }
+
+            fn try_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+
+            fn try_div(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_div(*self, *other)
+            }
+
+            fn try_rem(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_rem(*self, *other)
+            }
+
+            fn try_pow(&self, exp: &Self) -> Option<Self> {
+                match (*self, *exp) {
+                    (_, 0) => Some(1),
+                    (0 | 1, _) => Some(*self),
+                    (base, exp) => u32::try_from(exp).ok().and_then(|exp| base.checked_pow(exp)),
+                }
             }
-            continue;
         }
+    )*};
+}
+
+impl_number!(u64, u128);
 
-        // Build number from vertical digits
-        let mut num_str = String::new();
-        for row in 0..n_rows {
-            num_str.push_str(lines[row][i..=i].trim());
+impl Number for BigUint {
+    fn zero() -> Self {
+        BigUint::ZERO
+    }
+
+    fn try_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn try_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_sub(other)
+    }
+
+    fn try_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn try_div(&self, other: &Self) -> Option<Self> {
+        self.checked_div(other)
+    }
+
+    fn try_rem(&self, other: &Self) -> Option<Self> {
+        (!other.is_zero()).then(|| self % other)
+    }
+
+    fn try_pow(&self, exp: &Self) -> Option<Self> {
+        if exp.is_zero() {
+            Some(BigUint::one())
+        } else if self <= &BigUint::one() {
+            Some(self.clone())
+        } else {
+            exp.to_u32().map(|exp| self.pow(exp))
         }
+    }
+}
+
+/// A binary operation, folded left to right over a problem's operands.
+pub type Operation<N> = Box<dyn Fn(N, N) -> Result<N, ProblemError>>;
+
+fn checked<N>(result: Option<N>) -> Result<N, ProblemError> {
+    result.ok_or(ProblemError::Overflow)
+}
+
+/// Maps operator symbols on the worksheet's last line to their operations.
+pub struct OperatorTable<N = u64> {
+    ops: HashMap<String, Operation<N>>,
+}
 
-        current_col.push(num_str.parse().unwrap());
+impl<N: Number> Default for OperatorTable<N> {
+    /// `+ - * / % ^ max min`, all with checked arithmetic.
+    fn default() -> Self {
+        let mut table = OperatorTable { ops: HashMap::new() };
+        table.register("+", |a: N, b: N| checked(a.try_add(&b)));
+        table.register("-", |a: N, b: N| checked(a.try_sub(&b)));
+        table.register("*", |a: N, b: N| checked(a.try_mul(&b)));
+        table.register("/", |a: N, b: N| a.try_div(&b).ok_or(ProblemError::DivisionByZero));
+        table.register("%", |a: N, b: N| a.try_rem(&b).ok_or(ProblemError::DivisionByZero));
+        table.register("^", |a: N, b: N| checked(a.try_pow(&b)));
+        table.register("max", |a: N, b: N| Ok(a.max(b)));
+        table.register("min", |a: N, b: N| Ok(a.min(b)));
+        table
     }
-    if !current_col.is_empty() {
-        num_cols.push(current_col);
+}
+
+impl<N: Number> OperatorTable<N> {
+    /// Adds or replaces the operation for `symbol`.
+    pub fn register<F>(&mut self, symbol: &str, op: F)
+    where
+        F: Fn(N, N) -> Result<N, ProblemError> + 'static,
+    {
+        self.ops.insert(symbol.to_string(), Box::new(op));
     }
 
-    ops_line
+    /// Folds `operands` left to right with the operation for `symbol`.
+    pub fn evaluate(&self, symbol: &str, operands: &[N]) -> Result<N, ProblemError> {
+        let op = self
+            .ops
+            .get(symbol)
+            .ok_or_else(|| ProblemError::UnknownOperator(symbol.to_string()))?;
+
+        let (first, rest) = operands.split_first().ok_or(ProblemError::NoOperands)?;
+        rest.iter().try_fold(first.clone(), |acc, n| op(acc, n.clone()))
+    }
+}
+
+/// One worksheet problem: where it sits, its operands in reading order and its operator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem<N = u64> {
+    /// Character columns the problem's digits occupy.
+    pub columns: Range<usize>,
+    pub operands: Vec<N>,
+    pub operator: String,
+}
+
+/// Evaluates every problem independently so one failure doesn't hide the others.
+pub fn evaluate_each<N: Number>(problems: &[Problem<N>], table: &OperatorTable<N>) -> Vec<Result<N, ProblemError>> {
+    problems
         .iter()
+        .map(|p| table.evaluate(&p.operator, &p.operands))
+        .collect()
+}
+
+/// Sum of all problem results, reporting the first problem that fails.
+pub fn grand_total<N: Number>(problems: &[Problem<N>], table: &OperatorTable<N>) -> Result<N, WorksheetError> {
+    evaluate_each(problems, table)
+        .into_iter()
         .enumerate()
-        .map(|(i, op)| match op {
-            '*' => num_cols[i].iter().product::<u64>(),
-            '+' => num_cols[i].iter().sum::<u64>(),
-            _ => panic!("Unknown operator: {}", op),
+        .try_fold(N::zero(), |total, (index, result)| {
+            let value = result.map_err(|error| WorksheetError::Problem { index, error })?;
+            total.try_add(&value).ok_or(WorksheetError::TotalOverflow)
         })
-        .sum()
+}
+
+/// How operands are read out of a problem block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reading {
+    /// Each line of the block is one operand.
+    Rows,
+    /// Each column of digits, read top to bottom, is one operand.
+    ColumnsLeftToRight,
+    /// As `ColumnsLeftToRight`, but the rightmost column comes first.
+    ColumnsRightToLeft,
+}
+
+/// Splits the worksheet into problem blocks separated by all-space columns and
+/// reads each block according to `reading`.
+///
+/// The last non-blank line holds the operators; each block's operator is the
+/// token starting inside its columns, so it may spill into the gap after it.
+/// Short lines are treated as padded with spaces.
+pub fn parse_worksheet<N: Number>(input: &str, reading: Reading) -> Result<Vec<Problem<N>>, WorksheetError> {
+    let mut lines: Vec<Vec<char>> = input
+        .lines()
+        .map(|line| line.trim_end().chars().collect())
+        .collect();
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    let Some(ops_line) = lines.pop() else {
+        return Ok(Vec::new());
+    };
+
+    let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    let cell = |row: &[char], col: usize| row.get(col).copied().unwrap_or(' ');
+    let is_gap = |col: usize| lines.iter().all(|row| cell(row, col) == ' ');
+
+    // ---- Segment into blocks of consecutive non-gap columns ----
+    let mut spans = Vec::new();
+    let mut start = None;
+    for col in 0..=width {
+        match (start, col == width || is_gap(col)) {
+            (None, false) => start = Some(col),
+            (Some(s), true) => {
+                spans.push(s..col);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    // ---- Operator tokens with the column they start at ----
+    let mut tokens = Vec::new();
+    let mut col = 0;
+    while col < ops_line.len() {
+        if ops_line[col] == ' ' {
+            col += 1;
+            continue;
+        }
+        let begin = col;
+        while col < ops_line.len() && ops_line[col] != ' ' {
+            col += 1;
+        }
+        tokens.push((begin, ops_line[begin..col].iter().collect::<String>()));
+    }
+
+    spans
+        .into_iter()
+        .enumerate()
+        .map(|(index, span)| {
+            let mut ops = tokens.iter().filter(|(begin, _)| span.contains(begin));
+            let operator = match (ops.next(), ops.next()) {
+                (Some((_, op)), None) => op.clone(),
+                _ => return Err(WorksheetError::MissingOperator { index }),
+            };
+
+            let texts: Vec<String> = match reading {
+                Reading::Rows => lines
+                    .iter()
+                    .map(|row| span.clone().map(|c| cell(row, c)).collect())
+                    .collect(),
+                Reading::ColumnsLeftToRight | Reading::ColumnsRightToLeft => {
+                    let mut cols: Vec<String> = span
+                        .clone()
+                        .map(|c| lines.iter().map(|row| cell(row, c)).collect())
+                        .collect();
+                    if reading == Reading::ColumnsRightToLeft {
+                        cols.reverse();
+                    }
+                    cols
+                }
+            };
+
+            let operands = texts
+                .iter()
+                .map(|text| text.replace(' ', ""))
+                .filter(|text| !text.is_empty())
+                .map(|text| {
+                    text.parse::<N>()
+                        .map_err(|_| WorksheetError::InvalidOperand { index, text })
+                })
+                .collect::<Result<Vec<N>, _>>()?;
+
+            Ok(Problem { columns: span, operands, operator })
+        })
+        .collect()
+}
+
+/// A problem together with its evaluated result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solved<N = u64> {
+    pub problem: Problem<N>,
+    pub result: Result<N, ProblemError>,
+}
+
+/// Parses and evaluates every problem, keeping failures alongside successes.
+pub fn breakdown<N: Number>(
+    input: Option<&str>,
+    reading: Reading,
+    table: &OperatorTable<N>,
+) -> Result<Vec<Solved<N>>, WorksheetError> {
+    let normalized_input = input
+        .unwrap_or(include_str!("../input/day6.txt"))
+        .replace("\r\n", "\n");
+
+    let problems = parse_worksheet(&normalized_input, reading)?;
+    let results = evaluate_each(&problems, table);
+
+    Ok(problems
+        .into_iter()
+        .zip(results)
+        .map(|(problem, result)| Solved { problem, result })
+        .collect())
+}
+
+/// Renders each problem as an equation, e.g. `cols 0-2: 123 * 45 * 6 = 33210`.
+pub fn render_breakdown<N: Number>(solved: &[Solved<N>]) -> String {
+    let mut out = String::new();
+    for s in solved {
+        let operands: Vec<String> = s.problem.operands.iter().map(|n| n.to_string()).collect();
+        let result = match &s.result {
+            Ok(value) => value.to_string(),
+            Err(e) => format!("<{}>", e),
+        };
+        let Range { start, end } = s.problem.columns;
+        out.push_str(&format!(
+            "cols {}-{}: {} = {}\n",
+            start,
+            end - 1,
+            operands.join(&format!(" {} ", s.problem.operator)),
+            result
+        ));
+    }
+    out
+}
+
+fn silver_star(input: Option<&str>) -> u64 {
+    silver_star_with(input, &OperatorTable::default()).unwrap()
+}
+
+pub fn silver_star_with<N: Number>(input: Option<&str>, table: &OperatorTable<N>) -> Result<N, WorksheetError> {
+    let normalized_input = input
+        .unwrap_or(include_str!("../input/day6.txt"))
+        .replace("\r\n", "\n");
+
+    grand_total(&parse_worksheet(&normalized_input, Reading::Rows)?, table)
+}
+
+pub fn gold_star(input: Option<&str>) -> u64 {
+    gold_star_with(input, &OperatorTable::default()).unwrap()
+}
+
+pub fn gold_star_with<N: Number>(input: Option<&str>, table: &OperatorTable<N>) -> Result<N, WorksheetError> {
+    let normalized_input = input
+        .unwrap_or(include_str!("../input/day6.txt"))
+        .replace("\r\n", "\n");
+
+    grand_total(&parse_worksheet(&normalized_input, Reading::ColumnsLeftToRight)?, table)
 }
 
 #[cfg(test)]
@@ -96,4 +422,118 @@ mod tests {
         assert_eq!(gold_star(Some(TEST_INPUT)), 3263827);
         println!("{}", gold_star(None));
     }
+
+    #[test]
+    fn test_operators() {
+        let table = OperatorTable::<u64>::default();
+        assert_eq!(table.evaluate("-", &[20, 5, 3]), Ok(12));
+        assert_eq!(table.evaluate("/", &[100, 5, 2]), Ok(10));
+        assert_eq!(table.evaluate("%", &[17, 5]), Ok(2));
+        assert_eq!(table.evaluate("^", &[2, 3, 2]), Ok(64));
+        assert_eq!(table.evaluate("max", &[4, 9, 1]), Ok(9));
+        assert_eq!(table.evaluate("min", &[4, 9, 1]), Ok(1));
+
+        assert_eq!(table.evaluate("-", &[1, 2]), Err(ProblemError::Overflow));
+        assert_eq!(table.evaluate("/", &[1, 0]), Err(ProblemError::DivisionByZero));
+        assert_eq!(table.evaluate("^", &[10, 20]), Err(ProblemError::Overflow));
+        assert_eq!(table.evaluate("?", &[1]), Err(ProblemError::UnknownOperator("?".to_string())));
+    }
+
+    #[test]
+    fn test_worksheet_errors() {
+        let mut table = OperatorTable::<u64>::default();
+        table.register("gcd", |mut a, mut b| {
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            Ok(a)
+        });
+
+        let worksheet = indoc! {"
+        12 99999999999 7
+        18 99999999999 3
+        gcd  *         min
+        "};
+        let problems = parse_worksheet(worksheet, Reading::Rows).unwrap();
+        let results = evaluate_each(&problems, &table);
+        assert_eq!(results, vec![Ok(6), Err(ProblemError::Overflow), Ok(3)]);
+
+        assert_eq!(
+            silver_star_with(Some(worksheet), &table),
+            Err(WorksheetError::Problem { index: 1, error: ProblemError::Overflow })
+        );
+    }
+
+    #[test]
+    fn test_reading_directions() {
+        let problems = parse_worksheet::<u64>(TEST_INPUT, Reading::ColumnsRightToLeft).unwrap();
+        assert_eq!(problems[0].operands, vec![356, 24, 1]);
+        assert_eq!(problems[3].operands, vec![4, 431, 623]);
+
+        let problems = parse_worksheet::<u64>(TEST_INPUT, Reading::ColumnsLeftToRight).unwrap();
+        assert_eq!(problems[3].operands, vec![623, 431, 4]);
+
+        let problems = parse_worksheet::<u64>(TEST_INPUT, Reading::Rows).unwrap();
+        assert_eq!(
+            problems[1],
+            Problem { columns: 4..7, operands: vec![328, 64, 98], operator: "+".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_ragged_lines() {
+        // Trailing spaces stripped, so the last column is shorter on some lines
+        let worksheet = "123 328  51 64\n 45 64  387 23\n  6 98  215 314\n*   +   *   +\n\n";
+        assert_eq!(silver_star(Some(worksheet)), 4277556);
+        assert_eq!(gold_star(Some(worksheet)), 3263827);
+
+        assert_eq!(
+            parse_worksheet::<u64>("1 2\n3 4\n+", Reading::Rows),
+            Err(WorksheetError::MissingOperator { index: 1 })
+        );
+        assert_eq!(
+            parse_worksheet::<u64>("1 x\n+ +", Reading::Rows),
+            Err(WorksheetError::InvalidOperand { index: 1, text: "x".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_breakdown() {
+        let table = OperatorTable::<u64>::default();
+        let solved = breakdown(Some(TEST_INPUT), Reading::ColumnsRightToLeft, &table).unwrap();
+        let total: u64 = solved.iter().map(|s| s.result.clone().unwrap()).sum();
+        assert_eq!(total, 3263827);
+
+        let rendered = render_breakdown(&solved);
+        assert_eq!(rendered.lines().next(), Some("cols 0-2: 356 * 24 * 1 = 8544"));
+        assert_eq!(rendered.lines().last(), Some("cols 12-14: 4 + 431 + 623 = 1058"));
+
+        let overflow = breakdown(Some("99999999999\n99999999999\n*"), Reading::Rows, &table).unwrap();
+        assert_eq!(render_breakdown(&overflow), "cols 0-10: 99999999999 * 99999999999 = <arithmetic overflow>\n");
+    }
+
+    #[test]
+    fn test_big_numbers() {
+        let big = OperatorTable::<BigUint>::default();
+        assert_eq!(silver_star_with(None, &big).unwrap(), BigUint::from(silver_star(None)));
+        assert_eq!(gold_star_with(None, &big).unwrap(), BigUint::from(gold_star(None)));
+
+        let worksheet = indoc! {"
+        99999999999 18446744073709551615 2
+        99999999999                    1 200
+        *           +                    ^
+        "};
+        assert!(silver_star_with(Some(worksheet), &OperatorTable::<u64>::default()).is_err());
+        assert_eq!(
+            silver_star_with(Some(worksheet), &OperatorTable::<u128>::default()),
+            Err(WorksheetError::Problem { index: 2, error: ProblemError::Overflow })
+        );
+
+        let total = silver_star_with(Some(worksheet), &big).unwrap();
+        let expected = BigUint::from(99999999999u64).pow(2) + BigUint::from(u64::MAX) + 1u32 + BigUint::from(2u32).pow(200);
+        assert_eq!(total, expected);
+
+        assert_eq!(big.evaluate("-", &[BigUint::from(1u32), BigUint::from(2u32)]), Err(ProblemError::Overflow));
+        assert_eq!(big.evaluate("%", &[BigUint::from(1u32), BigUint::ZERO]), Err(ProblemError::DivisionByZero));
+    }
 }
@@ -1,101 +1,686 @@
-use std::collections::HashSet;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 
-fn silver_star(input: Option<&str>) -> i32 {
-    let normalized_input = input
-        .unwrap_or(include_str!("../input/day7.txt"))
-        .replace("\r\n", "\n");
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
 
-    let mut splits = 0;
+impl Direction {
+    const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
 
-    // ---- Initialize beams correctly ----
-    let start_index = normalized_input
+/// Behaviour of a manifold cell for a beam entering it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    /// Beam carries on in the same direction.
+    Empty,
+    /// Where the beam enters, heading down; otherwise behaves like `Empty`.
+    Start,
+    /// Any beam is replaced by two downward beams in the columns either side.
+    Splitter,
+    /// `/`: down <-> left, up <-> right.
+    MirrorSlash,
+    /// `\`: down <-> right, up <-> left.
+    MirrorBackslash,
+    /// Beam stops here.
+    Absorber,
+    /// Beam passes only when travelling in the given direction, otherwise stops.
+    Gate(Direction),
+}
+
+impl Tile {
+    /// Cells (as offsets from this one) and directions of the beams leaving
+    /// this tile for a beam entering it travelling `dir`.
+    fn outputs(self, dir: Direction) -> Vec<((isize, isize), Direction)> {
+        let straight = |d: Direction| vec![(d.delta(), d)];
+        match self {
+            Tile::Empty | Tile::Start => straight(dir),
+            Tile::Splitter => vec![((1, -1), Direction::Down), ((1, 1), Direction::Down)],
+            Tile::MirrorSlash => straight(match dir {
+                Direction::Down => Direction::Left,
+                Direction::Left => Direction::Down,
+                Direction::Up => Direction::Right,
+                Direction::Right => Direction::Up,
+            }),
+            Tile::MirrorBackslash => straight(match dir {
+                Direction::Down => Direction::Right,
+                Direction::Right => Direction::Down,
+                Direction::Up => Direction::Left,
+                Direction::Left => Direction::Up,
+            }),
+            Tile::Absorber => Vec::new(),
+            Tile::Gate(allowed) if allowed == dir => straight(dir),
+            Tile::Gate(_) => Vec::new(),
+        }
+    }
+}
+
+/// Maps manifold characters to tile behaviours.
+pub struct TileRegistry {
+    tiles: HashMap<char, Tile>,
+}
+
+impl Default for TileRegistry {
+    /// `.` `S` `^` `/` `\` `#` and the gates `>` `<` `v`.
+    fn default() -> Self {
+        let mut registry = TileRegistry { tiles: HashMap::new() };
+        registry.register('.', Tile::Empty);
+        registry.register('S', Tile::Start);
+        registry.register('^', Tile::Splitter);
+        registry.register('/', Tile::MirrorSlash);
+        registry.register('\\', Tile::MirrorBackslash);
+        registry.register('#', Tile::Absorber);
+        registry.register('>', Tile::Gate(Direction::Right));
+        registry.register('<', Tile::Gate(Direction::Left));
+        registry.register('v', Tile::Gate(Direction::Down));
+        registry
+    }
+}
+
+impl TileRegistry {
+    /// The original puzzle's tiles only: `.`, `S` and `^`.
+    pub fn downward_only() -> Self {
+        let mut registry = TileRegistry { tiles: HashMap::new() };
+        registry.register('.', Tile::Empty);
+        registry.register('S', Tile::Start);
+        registry.register('^', Tile::Splitter);
+        registry
+    }
+
+    /// Adds or replaces the tile for `symbol`.
+    pub fn register(&mut self, symbol: char, tile: Tile) {
+        self.tiles.insert(symbol, tile);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ManifoldError {
+    UnknownTile { row: usize, col: usize, symbol: char },
+    NoStart,
+    /// A beam can return to a state it was already in, so timelines are unbounded.
+    Cycle,
+    Overflow,
+}
+
+impl fmt::Display for ManifoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifoldError::UnknownTile { row, col, symbol } => {
+                write!(f, "unknown tile {:?} at row {}, column {}", symbol, row, col)
+            }
+            ManifoldError::NoStart => write!(f, "manifold has no start tile"),
+            ManifoldError::Cycle => write!(f, "beams can loop forever"),
+            ManifoldError::Overflow => write!(f, "timeline count overflows"),
+        }
+    }
+}
+
+impl std::error::Error for ManifoldError {}
+
+/// A beam in cell `(row, col)` travelling `dir`.
+type BeamState = (usize, usize, Direction);
+
+/// Where a beam leaving the grid goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Leave {
+    Side(Side),
+    Bottom,
+    Top,
+}
+
+/// A tachyon manifold whose beams may travel in any direction.
+pub struct Manifold {
+    tiles: Vec<Vec<Tile>>,
+    width: usize,
+    start: (usize, usize),
+    boundary: Boundary,
+}
+
+impl Manifold {
+    /// Parses `input` with `registry`; short rows are padded with empty tiles.
+    /// Beams leaving the sides are dropped until [`Manifold::with_boundary`].
+    pub fn parse(input: &str, registry: &TileRegistry) -> Result<Self, ManifoldError> {
+        let mut start = None;
+        let mut tiles = Vec::new();
+
+        for (row, line) in input.lines().enumerate() {
+            let mut tile_row = Vec::new();
+            for (col, symbol) in line.trim_end_matches('\r').chars().enumerate() {
+                let tile = *registry
+                    .tiles
+                    .get(&symbol)
+                    .ok_or(ManifoldError::UnknownTile { row, col, symbol })?;
+                if tile == Tile::Start && start.is_none() {
+                    start = Some((row, col));
+                }
+                tile_row.push(tile);
+            }
+            tiles.push(tile_row);
+        }
+
+        let width = tiles.iter().map(|r| r.len()).max().unwrap_or(0);
+        for tile_row in &mut tiles {
+            tile_row.resize(width, Tile::Empty);
+        }
+
+        let start = start.ok_or(ManifoldError::NoStart)?;
+        Ok(Manifold { tiles, width, start, boundary: Boundary::Drop })
+    }
+
+    /// Sets what happens to any beam pushed past the left or right edge, as
+    /// in [`map_timelines`]. A reflected sideways beam also turns around.
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// States reached from `state`, or where the beam leaves the grid. The
+    /// column is settled first, so a beam pushed diagonally past a corner
+    /// leaves through the side, as split beams do in [`map_timelines`].
+    fn successors(&self, (row, col, dir): BeamState) -> Vec<Result<BeamState, Leave>> {
+        self.tiles[row][col]
+            .outputs(dir)
+            .into_iter()
+            .map(|((dr, dc), next_dir)| {
+                let c = land(col, dc, self.width, self.boundary).map_err(Leave::Side)?;
+                let bounced = self.boundary == Boundary::Reflect && col.checked_add_signed(dc) != Some(c);
+                let next_dir = match next_dir {
+                    Direction::Left if bounced => Direction::Right,
+                    Direction::Right if bounced => Direction::Left,
+                    d => d,
+                };
+                match row.checked_add_signed(dr) {
+                    Some(r) if r < self.tiles.len() => Ok((r, c, next_dir)),
+                    Some(_) => Err(Leave::Bottom),
+                    None => Err(Leave::Top),
+                }
+            })
+            .collect()
+    }
+
+    fn start_state(&self) -> BeamState {
+        (self.start.0, self.start.1, Direction::Down)
+    }
+
+    /// Number of distinct splitters any beam reaches.
+    pub fn count_splits(&self) -> usize {
+        let mut seen = HashSet::from([self.start_state()]);
+        let mut stack = vec![self.start_state()];
+        let mut splitters = HashSet::new();
+
+        while let Some(state) = stack.pop() {
+            if self.tiles[state.0][state.1] == Tile::Splitter {
+                splitters.insert((state.0, state.1));
+            }
+            for next in self.successors(state).into_iter().flatten() {
+                if seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        splitters.len()
+    }
+
+    /// Number of distinct paths from the start that leave through the bottom row.
+    pub fn count_timelines(&self) -> Result<u64, ManifoldError> {
+        Ok(self.timeline_exits()?.bottom)
+    }
+
+    /// Number of distinct paths from the start leaving through each side.
+    /// Paths leaving through the top aren't counted anywhere.
+    pub fn timeline_exits(&self) -> Result<Exits<u64>, ManifoldError> {
+        let rows = self.tiles.len();
+        let idx = |(r, c, d): BeamState| (r * self.width + c) * Direction::ALL.len() + d.index();
+
+        // 0 = unvisited, 1 = on the DFS stack, 2 = done
+        let mut mark = vec![0u8; rows * self.width * Direction::ALL.len()];
+        let mut ways = vec![Exits::<u64>::default(); mark.len()];
+        let add = |slot: &mut u64, n: u64| -> Result<(), ManifoldError> {
+            *slot = slot.checked_add(n).ok_or(ManifoldError::Overflow)?;
+            Ok(())
+        };
+
+        // Iterative post-order DFS so deep manifolds cannot overflow the call stack
+        let mut stack = vec![(self.start_state(), false)];
+        while let Some((state, expanded)) = stack.pop() {
+            let i = idx(state);
+            if expanded {
+                let mut total = Exits::default();
+                for next in self.successors(state) {
+                    match next {
+                        Ok(next) => {
+                            let from = ways[idx(next)];
+                            add(&mut total.left, from.left)?;
+                            add(&mut total.right, from.right)?;
+                            add(&mut total.bottom, from.bottom)?;
+                        }
+                        Err(Leave::Side(Side::Left)) => add(&mut total.left, 1)?,
+                        Err(Leave::Side(Side::Right)) => add(&mut total.right, 1)?,
+                        Err(Leave::Bottom) => add(&mut total.bottom, 1)?,
+                        Err(Leave::Top) => {}
+                    }
+                }
+                ways[i] = total;
+                mark[i] = 2;
+                continue;
+            }
+
+            match mark[i] {
+                1 => return Err(ManifoldError::Cycle),
+                2 => continue,
+                _ => {}
+            }
+            mark[i] = 1;
+            stack.push((state, true));
+            for next in self.successors(state).into_iter().flatten() {
+                match mark[idx(next)] {
+                    0 => stack.push((next, false)),
+                    1 => return Err(ManifoldError::Cycle),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(ways[idx(self.start_state())])
+    }
+}
+
+/// Column of `S` in the first line and the widest line's length.
+fn start_and_width(input: &str) -> Result<(usize, usize), ManifoldError> {
+    let start = input
         .lines()
         .next()
-        .unwrap()
-        .char_indices()
-        .find(|&(_, c)| c == 'S')
-        .unwrap()
-        .0;
-
-    let mut beams: HashSet<usize> = HashSet::new();
-    beams.insert(start_index);
-
-    // ---- Iterate through remaining lines ----
-    for line in normalized_input.lines().skip(1) {
-        let chars: Vec<char> = line.chars().collect();
-        let mut beams_next: HashSet<usize> = HashSet::new();
-
-        for &beam in &beams {
-            match chars[beam] {
-                '^' => {
-                    println!("Beam at {} split!", beam);
-                    beams_next.insert(beam - 1);
-                    beams_next.insert(beam + 1);
+        .and_then(|line| line.find('S'))
+        .ok_or(ManifoldError::NoStart)?;
+    let width = input.lines().map(str::len).max().unwrap_or(0);
+    Ok((start, width))
+}
+
+/// What happens to a split beam pushed past the left or right edge.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// The beam leaves the manifold through that side.
+    #[default]
+    Drop,
+    /// The beam re-enters on the opposite edge.
+    Wrap,
+    /// The beam bounces back into the edge column.
+    Reflect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Column a beam emitted `offset` columns from `col` lands in, or the side it leaves through.
+fn land(col: usize, offset: isize, width: usize, boundary: Boundary) -> Result<usize, Side> {
+    match col.checked_add_signed(offset) {
+        Some(c) if c < width => Ok(c),
+        landed => {
+            let side = if landed.is_none() { Side::Left } else { Side::Right };
+            match (boundary, side) {
+                (Boundary::Drop, _) => Err(side),
+                (Boundary::Wrap, Side::Left) => Ok(width - 1),
+                (Boundary::Wrap, Side::Right) => Ok(0),
+                (Boundary::Reflect, Side::Left) => Ok(0),
+                (Boundary::Reflect, Side::Right) => Ok(width - 1),
+            }
+        }
+    }
+}
+
+/// How many beams or timelines left the manifold through each side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exits<T> {
+    pub left: T,
+    pub right: T,
+    pub bottom: T,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BeamReport {
+    pub splits: usize,
+    /// Beams leaving the sides are counted per split; `bottom` is the number
+    /// of distinct beams after the last row.
+    pub exits: Exits<usize>,
+}
+
+/// Follows the set of beam columns row by row, counting splits and exits.
+pub fn trace_beams(input: &str, boundary: Boundary) -> Result<BeamReport, ManifoldError> {
+    let (start_index, width) = start_and_width(input)?;
+
+    let mut splits = 0;
+    let mut exits = Exits::default();
+
+    let mut beams = vec![false; width];
+    let mut beams_next = vec![false; width];
+    beams[start_index] = true;
+
+    for line in input.lines().skip(1) {
+        let row = line.as_bytes();
+        beams_next.fill(false);
+
+        for (beam, _) in beams.iter().enumerate().filter(|&(_, &lit)| lit) {
+            match row.get(beam) {
+                Some(b'^') => {
                     splits += 1;
+                    for offset in [-1, 1] {
+                        match land(beam, offset, width, boundary) {
+                            Ok(col) => beams_next[col] = true,
+                            Err(Side::Left) => exits.left += 1,
+                            Err(Side::Right) => exits.right += 1,
+                        }
+                    }
                 }
                 _ => {
-                    beams_next.insert(beam);
+                    beams_next[beam] = true;
                 }
             }
         }
 
-        beams = beams_next;
+        std::mem::swap(&mut beams, &mut beams_next);
     }
 
-    splits
+    exits.bottom = beams.iter().filter(|&&lit| lit).count();
+    Ok(BeamReport { splits, exits })
 }
 
-fn gold_star(input: Option<&str>) -> u64 {
-    let normalized_input = input
-        .unwrap_or(include_str!("../input/day7.txt"))
-        .replace("\r\n", "\n");
+/// Where timelines went: per-column counts leaving the bottom and per-splitter hits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimelineMap {
+    /// Timelines ending in each column after the last row.
+    pub final_columns: Vec<u64>,
+    /// Timelines reaching each splitter, keyed by `(row, col)`; 0 if never hit.
+    pub splitter_hits: BTreeMap<(usize, usize), u64>,
+    pub exits: Exits<u64>,
+}
 
-    // ---- Initialize beams correctly ----
-    let start_index = normalized_input
-        .lines()
-        .next()
-        .unwrap()
-        .char_indices()
-        .find(|&(_, c)| c == 'S')
-        .unwrap()
-        .0;
+/// Row-by-row timeline count over a dense column vector, reporting overflow
+/// instead of wrapping, and recording where the timelines went.
+pub fn map_timelines(input: &str, boundary: Boundary) -> Result<TimelineMap, ManifoldError> {
+    let (start_index, width) = start_and_width(input)?;
+
+    // column -> number of timelines reaching that column at current row
+    let mut counts = vec![0u64; width];
+    let mut next_counts = vec![0u64; width];
+    counts[start_index] = 1; // single particle starts -> 1 timeline
 
-    // map: column -> number of timelines reaching that column at current row
-    let mut counts: HashMap<usize, u64> = HashMap::new();
-    counts.insert(start_index, 1); // single particle starts -> 1 timeline
+    let mut exits = Exits::default();
+    let mut splitter_hits = BTreeMap::new();
 
-    // iterate remaining lines
-    for line in normalized_input.lines().skip(1) {
-        let chars: Vec<char> = line.chars().collect();
-        let width = chars.len();
+    let add = |slot: &mut u64, ways: u64| -> Result<(), ManifoldError> {
+        *slot = slot.checked_add(ways).ok_or(ManifoldError::Overflow)?;
+        Ok(())
+    };
 
-        let mut next_counts: HashMap<usize, u64> = HashMap::new();
+    for (r, line) in input.lines().enumerate().skip(1) {
+        let row = line.as_bytes();
+        next_counts.fill(0);
 
-        for (&col, &ways) in &counts {
+        for (c, _) in row.iter().enumerate().filter(|&(_, &b)| b == b'^') {
+            splitter_hits.insert((r, c), counts.get(c).copied().unwrap_or(0));
+        }
 
-            match chars[col] {
-                '^' => {
+        for (col, &ways) in counts.iter().enumerate().filter(|&(_, &ways)| ways > 0) {
+            match row.get(col) {
+                Some(b'^') => {
                     //add the current timeline to the splits in the next row
-                    if col > 0 {
-                        *next_counts.entry(col - 1).or_insert(0) += ways;
-                    }
-                    if col + 1 < width {
-                        *next_counts.entry(col + 1).or_insert(0) += ways;
+                    for offset in [-1, 1] {
+                        match land(col, offset, width, boundary) {
+                            Ok(c) => add(&mut next_counts[c], ways)?,
+                            Err(Side::Left) => add(&mut exits.left, ways)?,
+                            Err(Side::Right) => add(&mut exits.right, ways)?,
+                        }
                     }
                 }
                 _ => {
                     // continue straight down (same column)
-                    *next_counts.entry(col).or_insert(0) += ways;
+                    add(&mut next_counts[col], ways)?;
                 }
             }
         }
 
-        counts = next_counts;
+        std::mem::swap(&mut counts, &mut next_counts);
     }
 
     // total timelines after finishing all rows:
-    counts.values().copied().sum()
+    exits.bottom = counts
+        .iter()
+        .try_fold(0u64, |total, &ways| total.checked_add(ways))
+        .ok_or(ManifoldError::Overflow)?;
+
+    Ok(TimelineMap { final_columns: counts, splitter_hits, exits })
+}
+
+/// Timeline exits only; `exits.bottom` is the number of complete timelines.
+pub fn trace_timelines(input: &str, boundary: Boundary) -> Result<Exits<u64>, ManifoldError> {
+    Ok(map_timelines(input, boundary)?.exits)
+}
+
+/// The manifold with every hit splitter replaced by its hit count and a final
+/// row of per-column bottom exits. Unhit splitters stay `^`, zeros show as `.`.
+pub fn render_timeline_map(input: &str, map: &TimelineMap) -> String {
+    let cell_width = map
+        .splitter_hits
+        .values()
+        .chain(&map.final_columns)
+        .map(|n| n.to_string().len())
+        .max()
+        .unwrap_or(1);
+
+    let mut out = String::new();
+    let mut push_row = |cells: Vec<String>| {
+        let cells: Vec<String> = cells.iter().map(|c| format!("{:>w$}", c, w = cell_width)).collect();
+        out.push_str(cells.join(" ").trim_end());
+        out.push('\n');
+    };
+
+    for (r, line) in input.lines().enumerate() {
+        push_row(
+            line.chars()
+                .enumerate()
+                .map(|(c, ch)| match map.splitter_hits.get(&(r, c)) {
+                    Some(&hits) if hits > 0 => hits.to_string(),
+                    _ => ch.to_string(),
+                })
+                .collect(),
+        );
+    }
+
+    push_row(
+        map.final_columns
+            .iter()
+            .map(|&n| if n == 0 { ".".to_string() } else { n.to_string() })
+            .collect(),
+    );
+
+    out
+}
+
+/// Annotated timeline grid for `input`, or for the bundled puzzle input.
+pub fn timeline_map(input: Option<&str>, boundary: Boundary) -> Result<String, ManifoldError> {
+    let normalized_input = input
+        .unwrap_or(include_str!("../input/day7.txt"))
+        .replace("\r\n", "\n");
+
+    let map = map_timelines(&normalized_input, boundary)?;
+    Ok(render_timeline_map(&normalized_input, &map))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Choice {
+    Left,
+    Right,
+}
+
+/// The choice made at one splitter along a timeline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Split {
+    pub row: usize,
+    pub col: usize,
+    pub choice: Choice,
+}
+
+/// One complete timeline: its splitter choices in order and the column it leaves the bottom in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timeline {
+    pub splits: Vec<Split>,
+    pub exit_column: usize,
+}
+
+impl Timeline {
+    pub fn choices(&self) -> Vec<Choice> {
+        self.splits.iter().map(|s| s.choice).collect()
+    }
+}
+
+/// Small deterministic generator for sampling timelines (SplitMix64).
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`, rejecting the biased tail of the range.
+    /// Panics if `bound` is zero, since the range would be empty.
+    fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "SplitMix64::below needs a nonzero bound");
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % bound;
+            }
+        }
+    }
+}
+
+/// Suffix timeline counts for every row and column, used to pick out
+/// individual timelines without enumerating the others.
+pub struct TimelineIndex {
+    rows: Vec<Vec<u8>>,
+    /// `ways[r][c]`: complete timelines for a beam entering row `r` at column `c`.
+    ways: Vec<Vec<u64>>,
+    start: usize,
+    width: usize,
+    boundary: Boundary,
+}
+
+impl TimelineIndex {
+    pub fn build(input: &str, boundary: Boundary) -> Result<Self, ManifoldError> {
+        let (start, width) = start_and_width(input)?;
+        let rows: Vec<Vec<u8>> = input.lines().map(|l| l.as_bytes().to_vec()).collect();
+
+        // Past the last row every beam has left through the bottom
+        let mut ways = vec![vec![0u64; width]; rows.len() + 1];
+        ways[rows.len()].fill(1);
+
+        for r in (1..rows.len()).rev() {
+            for c in 0..width {
+                ways[r][c] = if rows[r].get(c) == Some(&b'^') {
+                    let below = |offset| land(c, offset, width, boundary).map_or(0, |c| ways[r + 1][c]);
+                    below(-1).checked_add(below(1)).ok_or(ManifoldError::Overflow)?
+                } else {
+                    ways[r + 1][c]
+                };
+            }
+        }
+
+        Ok(TimelineIndex { rows, ways, start, width, boundary })
+    }
+
+    /// Total number of complete timelines.
+    pub fn count(&self) -> u64 {
+        self.ways[1.min(self.rows.len())][self.start]
+    }
+
+    /// The `k`-th timeline (0-based) in lexicographic order of choices, with
+    /// `Left` before `Right`.
+    pub fn kth(&self, mut k: u64) -> Option<Timeline> {
+        if k >= self.count() {
+            return None;
+        }
+
+        let mut splits = Vec::new();
+        let mut col = self.start;
+        for r in 1..self.rows.len() {
+            if self.rows[r].get(col) != Some(&b'^') {
+                continue;
+            }
+
+            let left = land(col, -1, self.width, self.boundary);
+            let left_ways = left.map_or(0, |c| self.ways[r + 1][c]);
+            let choice = if k < left_ways {
+                Choice::Left
+            } else {
+                k -= left_ways;
+                Choice::Right
+            };
+
+            splits.push(Split { row: r, col, choice });
+            // The chosen side has timelines left, so it cannot have exited
+            col = match choice {
+                Choice::Left => left,
+                Choice::Right => land(col, 1, self.width, self.boundary),
+            }
+            .ok()?;
+        }
+
+        Some(Timeline { splits, exit_column: col })
+    }
+
+    /// A timeline chosen uniformly at random among all complete timelines.
+    pub fn sample(&self, rng: &mut SplitMix64) -> Option<Timeline> {
+        match self.count() {
+            0 => None,
+            total => self.kth(rng.below(total)),
+        }
+    }
+}
+
+fn silver_star(input: Option<&str>) -> i32 {
+    let normalized_input = input
+        .unwrap_or(include_str!("../input/day7.txt"))
+        .replace("\r\n", "\n");
+
+    trace_beams(&normalized_input, Boundary::Drop).unwrap().splits as i32
+}
+
+fn gold_star(input: Option<&str>) -> u64 {
+    gold_star_checked(input).unwrap()
+}
+
+pub fn gold_star_checked(input: Option<&str>) -> Result<u64, ManifoldError> {
+    let normalized_input = input
+        .unwrap_or(include_str!("../input/day7.txt"))
+        .replace("\r\n", "\n");
+
+    Ok(trace_timelines(&normalized_input, Boundary::Drop)?.bottom)
 }
 
 
@@ -135,4 +720,220 @@ mod tests {
         println!("{}", gold_star(None));
     }
 
+    const EDGE_INPUT: &str = indoc! {"
+    .S.
+    ...
+    .^.
+    ...
+    ^.^
+    ...
+    "};
+
+    #[test]
+    fn test_boundaries() {
+        // Both outer beams hit splitters on the edges
+        let drop = trace_beams(EDGE_INPUT, Boundary::Drop).unwrap();
+        assert_eq!(drop, BeamReport { splits: 3, exits: Exits { left: 1, right: 1, bottom: 1 } });
+        assert_eq!(trace_timelines(EDGE_INPUT, Boundary::Drop), Ok(Exits { left: 1, right: 1, bottom: 2 }));
+
+        let wrap = trace_beams(EDGE_INPUT, Boundary::Wrap).unwrap();
+        assert_eq!(wrap.exits, Exits { left: 0, right: 0, bottom: 3 });
+        assert_eq!(trace_timelines(EDGE_INPUT, Boundary::Wrap), Ok(Exits { left: 0, right: 0, bottom: 4 }));
+
+        let reflect = trace_beams(EDGE_INPUT, Boundary::Reflect).unwrap();
+        assert_eq!(reflect.exits, Exits { left: 0, right: 0, bottom: 3 });
+        assert_eq!(trace_timelines(EDGE_INPUT, Boundary::Reflect), Ok(Exits { left: 0, right: 0, bottom: 4 }));
+
+        assert_eq!(silver_star(Some(EDGE_INPUT)), 3);
+        assert_eq!(gold_star(Some(EDGE_INPUT)), 2);
+
+        // The general engine agrees with the row-by-row counts on every boundary
+        for boundary in [Boundary::Drop, Boundary::Wrap, Boundary::Reflect] {
+            for input in [EDGE_INPUT, TEST_INPUT, "S\n^"] {
+                let manifold = Manifold::parse(input, &TileRegistry::downward_only()).unwrap().with_boundary(boundary);
+                assert_eq!(manifold.timeline_exits(), trace_timelines(input, boundary));
+                assert_eq!(manifold.count_splits(), trace_beams(input, boundary).unwrap().splits);
+            }
+        }
+
+        // A sideways beam runs off the right edge, wraps onto the mirror, or bounces back up
+        let sideways = |boundary| {
+            let manifold = Manifold::parse("S..\n\\..\n...", &TileRegistry::default()).unwrap();
+            manifold.with_boundary(boundary).timeline_exits()
+        };
+        assert_eq!(sideways(Boundary::Drop), Ok(Exits { left: 0, right: 1, bottom: 0 }));
+        assert_eq!(sideways(Boundary::Wrap), Ok(Exits { left: 0, right: 0, bottom: 1 }));
+        assert_eq!(sideways(Boundary::Reflect), Ok(Exits::default()));
+    }
+
+    #[test]
+    fn test_timeline_map() {
+        let map = map_timelines(TEST_INPUT, Boundary::Drop).unwrap();
+        assert_eq!(map.final_columns.iter().sum::<u64>(), 40);
+        assert_eq!(map.final_columns, vec![1, 0, 2, 0, 10, 0, 11, 0, 11, 0, 2, 1, 1, 0, 1]);
+        assert_eq!(map.splitter_hits[&(2, 7)], 1);
+        assert_eq!(map.splitter_hits[&(4, 6)], 1);
+        assert_eq!(map.splitter_hits[&(14, 13)], 1);
+
+        // Splitters no beam ever reaches
+        let unhit: Vec<(usize, usize)> = map.splitter_hits.iter().filter(|&(_, &n)| n == 0).map(|(&k, _)| k).collect();
+        assert_eq!(unhit, vec![(14, 9)]);
+        assert_eq!(map.splitter_hits.len() - unhit.len(), silver_star(Some(TEST_INPUT)) as usize);
+
+        let rendered = render_timeline_map(TEST_INPUT, &map);
+        println!("{}", rendered);
+        assert_eq!(rendered.lines().nth(2), Some(" .  .  .  .  .  .  .  1  .  .  .  .  .  .  ."));
+        assert_eq!(rendered.lines().nth(14), Some(" .  1  .  1  .  4  .  7  .  ^  .  .  .  1  ."));
+        assert_eq!(rendered.lines().last(), Some(" 1  .  2  . 10  . 11  . 11  .  2  1  1  .  1"));
+        assert_eq!(timeline_map(Some(TEST_INPUT), Boundary::Drop), Ok(rendered));
+    }
+
+    #[test]
+    fn test_kth_timeline() {
+        let index = TimelineIndex::build(TEST_INPUT, Boundary::Drop).unwrap();
+        assert_eq!(index.count(), 40);
+
+        let all: Vec<Timeline> = (0..40).map(|k| index.kth(k).unwrap()).collect();
+        assert!(all.windows(2).all(|w| w[0].choices() < w[1].choices()));
+        assert!(all[0].choices().iter().all(|&c| c == Choice::Left));
+        assert!(all[39].choices().iter().all(|&c| c == Choice::Right));
+        assert_eq!(all[0].splits[0], Split { row: 2, col: 7, choice: Choice::Left });
+        assert_eq!(index.kth(40), None);
+
+        // Exit columns of every timeline match the per-column histogram
+        let map = map_timelines(TEST_INPUT, Boundary::Drop).unwrap();
+        let mut histogram = vec![0u64; map.final_columns.len()];
+        for timeline in &all {
+            histogram[timeline.exit_column] += 1;
+        }
+        assert_eq!(histogram, map.final_columns);
+
+        let real = TimelineIndex::build(include_str!("../input/day7.txt"), Boundary::Drop).unwrap();
+        assert_eq!(real.count(), gold_star(None));
+        assert!(real.kth(real.count() - 1).is_some());
+    }
+
+    #[test]
+    fn test_sample_timeline() {
+        let index = TimelineIndex::build(TEST_INPUT, Boundary::Drop).unwrap();
+        let all: Vec<Vec<Choice>> = (0..40).map(|k| index.kth(k).unwrap().choices()).collect();
+
+        let mut rng = SplitMix64(7);
+        let mut seen = vec![0; all.len()];
+        for _ in 0..4000 {
+            let choices = index.sample(&mut rng).unwrap().choices();
+            seen[all.iter().position(|c| *c == choices).unwrap()] += 1;
+        }
+        // Each timeline expects 100 hits
+        assert!(seen.iter().all(|&n| (50..150).contains(&n)), "{:?}", seen);
+
+        let none = TimelineIndex::build("S\n^", Boundary::Drop).unwrap();
+        assert_eq!(none.count(), 0);
+        assert_eq!(none.sample(&mut rng), None);
+    }
+
+    #[test]
+    fn test_downward_special_case() {
+        for registry in [TileRegistry::downward_only(), TileRegistry::default()] {
+            let manifold = Manifold::parse(TEST_INPUT, &registry).unwrap();
+            assert_eq!(manifold.count_splits(), 21);
+            assert_eq!(manifold.count_timelines(), Ok(40));
+        }
+
+        // Split beams pushed past either side are dropped, as in the puzzle
+        let manifold = Manifold::parse("S\n^", &TileRegistry::downward_only()).unwrap();
+        assert_eq!(manifold.count_timelines(), Ok(0));
+        assert_eq!(gold_star_checked(Some("S\n^")), Ok(0));
+        assert_eq!(TimelineIndex::build("S\n^", Boundary::Drop).unwrap().count(), 0);
+
+        let manifold = Manifold::parse(include_str!("../input/day7.txt"), &TileRegistry::default()).unwrap();
+        assert_eq!(manifold.count_splits(), silver_star(None) as usize);
+        assert_eq!(manifold.count_timelines(), Ok(gold_star(None)));
+    }
+
+    #[test]
+    fn test_mirrors_and_gates() {
+        // Left half is turned back down through a gate, right half is absorbed
+        let manifold = Manifold::parse(indoc! {"
+        ...S...
+        .......
+        ...^...
+        .//.#..
+        .......
+        .v.....
+        .......
+        "}, &TileRegistry::default()).unwrap();
+        assert_eq!(manifold.count_splits(), 1);
+        assert_eq!(manifold.count_timelines(), Ok(1));
+
+        // A gate facing the wrong way blocks the only path
+        let blocked = Manifold::parse("S\n<\n.", &TileRegistry::default()).unwrap();
+        assert_eq!(blocked.count_timelines(), Ok(0));
+
+        assert_eq!(
+            Manifold::parse("S\n<", &TileRegistry::downward_only()).err(),
+            Some(ManifoldError::UnknownTile { row: 1, col: 0, symbol: '<' })
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        // The right half of the split is mirrored round and back into the splitter
+        let manifold = Manifold::parse(indoc! {r"
+        S./.\
+        \.^..
+        ...\/
+        .....
+        "}, &TileRegistry::default()).unwrap();
+        assert_eq!(manifold.count_timelines(), Err(ManifoldError::Cycle));
+        assert_eq!(manifold.count_splits(), 1);
+    }
+
+    /// `rows` rows of splitters at pseudo-random columns, interleaved with empty rows.
+    fn synthetic_manifold(width: usize, rows: usize) -> String {
+        let mut rng = crate::TestRng::new(0x9e37_79b9_7f4a_7c15);
+        let mut out = String::with_capacity((width + 1) * rows * 2);
+        out.push_str(&".".repeat(width / 2));
+        out.push('S');
+        out.push_str(&".".repeat(width - width / 2 - 1));
+        out.push('\n');
+        for _ in 0..rows {
+            for col in 0..width {
+                let inner = col > 0 && col + 1 < width;
+                out.push(if rng.below(4) == 0 && inner { '^' } else { '.' });
+            }
+            out.push('\n');
+            out.push_str(&".".repeat(width));
+            out.push('\n');
+        }
+        out
+    }
+
+    #[test]
+    fn test_overflow() {
+        // Dense splitter rows roughly double the timelines every other row
+        let wide = synthetic_manifold(64, 400);
+        assert_eq!(gold_star_checked(Some(&wide)), Err(ManifoldError::Overflow));
+        let manifold = Manifold::parse(&wide, &TileRegistry::default()).unwrap();
+        assert_eq!(manifold.count_timelines(), Err(ManifoldError::Overflow));
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_wide_manifold() {
+        use std::time::Instant;
+
+        let input = synthetic_manifold(50_000, 100);
+
+        let start = Instant::now();
+        let splits = silver_star(Some(&input));
+        let timelines = gold_star_checked(Some(&input));
+        println!("dense rows: splits={} timelines={:?} in {:?}", splits, timelines, start.elapsed());
+
+        let start = Instant::now();
+        let manifold = Manifold::parse(&input, &TileRegistry::default()).unwrap();
+        let splits = manifold.count_splits();
+        let timelines = manifold.count_timelines();
+        println!("general engine: splits={} timelines={:?} in {:?}", splits, timelines, start.elapsed());
+    }
 }
@@ -1,142 +1,924 @@
-#[derive(Clone, Debug)]
-struct Pos {
-    x: i32,
-    y: i32,
-    z: i32,
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+
+/// A junction box position with `D` integer coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Point<const D: usize>(pub [i64; D]);
+
+/// Distance used to decide which boxes are closest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Metric {
+    /// Straight-line distance, kept squared so it stays an exact integer.
+    #[default]
+    Euclidean,
+    /// Sum of the per-axis gaps.
+    Manhattan,
+    /// Largest per-axis gap.
+    Chebyshev,
 }
 
-impl Pos {
-    fn dist2(&self, other: &Pos) -> i64 {
-        let dx = (self.x - other.x) as i64;
-        let dy = (self.y - other.y) as i64;
-        let dz = (self.z - other.z) as i64;
-        dx * dx + dy * dy + dz * dz
+/// Distance under a [`Metric`].
+///
+/// Manhattan and Chebyshev distances are exact for any `i64` coordinates.
+/// Squared Euclidean distances are exact while the sum of squared gaps fits
+/// in a `u128`, e.g. coordinates spanning under 2^63 in up to four
+/// dimensions; beyond that they saturate at `u128::MAX` and compare equal.
+pub type Distance = u128;
+
+impl Metric {
+    /// Folds per-axis absolute gaps into a distance.
+    fn combine(self, gaps: impl Iterator<Item = u64>) -> Distance {
+        gaps.map(Distance::from).fold(0, |acc, g| match self {
+            Metric::Euclidean => acc.saturating_add(g * g),
+            Metric::Manhattan => acc.saturating_add(g),
+            Metric::Chebyshev => acc.max(g),
+        })
+    }
+
+    pub fn distance<const D: usize>(self, a: &Point<D>, b: &Point<D>) -> Distance {
+        self.combine(a.0.iter().zip(&b.0).map(|(x, y)| x.abs_diff(*y)))
+    }
+
+    /// The true length of `dist`: square-rooted for Euclidean, as is otherwise.
+    pub fn length(self, dist: Distance) -> f64 {
+        match self {
+            Metric::Euclidean => (dist as f64).sqrt(),
+            Metric::Manhattan | Metric::Chebyshev => dist as f64,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Euclidean => "euclidean",
+            Metric::Manhattan => "manhattan",
+            Metric::Chebyshev => "chebyshev",
+        }
     }
 }
 
+/// Union-find over box indices that keeps the number of circuits and the
+/// multiset of circuit sizes up to date on every union.
 #[derive(Clone)]
 struct DSU {
     parent: Vec<usize>,
+    rank: Vec<u8>,
     size: Vec<usize>,
+    components: usize,
+    /// circuit size -> number of circuits with that size
+    size_counts: BTreeMap<usize, usize>,
 }
 
 impl DSU {
     fn new(n: usize) -> Self {
+        let mut size_counts = BTreeMap::new();
+        if n > 0 {
+            size_counts.insert(1, n);
+        }
         DSU {
             parent: (0..n).collect(),
+            rank: vec![0; n],
             size: vec![1; n],
+            components: n,
+            size_counts,
         }
     }
 
+    /// Root of `a`'s set, compressing the path behind it without recursion.
     fn find(&mut self, a: usize) -> usize {
-        if self.parent[a] != a {
-            self.parent[a] = self.find(self.parent[a]);
+        let mut root = a;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut node = a;
+        while self.parent[node] != root {
+            let next = self.parent[node];
+            self.parent[node] = root;
+            node = next;
         }
-        self.parent[a]
+
+        root
     }
 
     fn union(&mut self, a: usize, b: usize) -> bool {
-        let pa = self.find(a);
-        let pb = self.find(b);
+        let mut pa = self.find(a);
+        let mut pb = self.find(b);
 
         if pa == pb {
             return false;
         }
 
-        if self.size[pa] < self.size[pb] {
-            self.parent[pa] = pb;
-            self.size[pb] += self.size[pa];
-        } else {
-            self.parent[pb] = pa;
-            self.size[pa] += self.size[pb];
+        // Attach the shallower tree under the deeper one
+        if self.rank[pa] < self.rank[pb] {
+            std::mem::swap(&mut pa, &mut pb);
+        }
+        if self.rank[pa] == self.rank[pb] {
+            self.rank[pa] += 1;
+        }
+        self.parent[pb] = pa;
+
+        for old in [self.size[pa], self.size[pb]] {
+            self.remove_size(old);
         }
+        self.size[pa] += self.size[pb];
+        *self.size_counts.entry(self.size[pa]).or_insert(0) += 1;
+        self.components -= 1;
 
         true
     }
-    
-    fn component_sizes(&mut self) -> Vec<usize> {
-        let n = self.parent.len();
-        let mut freq = vec![0; n];
-        for i in 0..n {
-            let root = self.find(i);
-            freq[root] += 1;
+
+    fn remove_size(&mut self, size: usize) {
+        if let Some(count) = self.size_counts.get_mut(&size) {
+            *count -= 1;
+            if *count == 0 {
+                self.size_counts.remove(&size);
+            }
         }
-        let mut sizes: Vec<usize> = freq.into_iter().filter(|&x| x > 0).collect();
-        sizes.sort_unstable_by(|a, b| b.cmp(a));
-        sizes
+    }
+
+    fn components(&self) -> usize {
+        self.components
+    }
+
+    fn max_size(&self) -> usize {
+        self.size_counts.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Sizes of the `k` biggest circuits, largest first.
+    fn largest_sizes(&self, k: usize) -> Vec<usize> {
+        self.size_counts
+            .iter()
+            .rev()
+            .flat_map(|(&size, &count)| std::iter::repeat_n(size, count))
+            .take(k)
+            .collect()
+    }
+
+    /// Product of the `k` largest set sizes, or `None` if it overflows `i64`.
+    fn size_product(&self, k: usize) -> Option<i64> {
+        self.largest_sizes(k).iter().try_fold(1i64, |acc, &s| acc.checked_mul(s as i64))
+    }
+
+    fn component_sizes(&self) -> Vec<usize> {
+        self.largest_sizes(self.components)
     }
 }
 
-fn parse_positions(inp: &str) -> Vec<Pos> {
-    inp.lines()
-        .map(|line| {
-            let v: Vec<i32> = line.split(',').map(|s| s.parse().unwrap()).collect();
-            Pos { x: v[0], y: v[1], z: v[2] }
-        })
-        .collect()
+/// An edge between boxes `a < b`, ordered by distance and then by indices.
+type Edge = (Distance, usize, usize);
+
+fn edge(dist: Distance, i: usize, j: usize) -> Edge {
+    (dist, i.min(j), i.max(j))
 }
 
-pub fn silver_star(inp: Option<&str>) -> i64 {
-    let input = inp.unwrap_or(include_str!("../input/day8.txt")).replace("\r\n", "\n");
+const LEAF_SIZE: usize = 8;
+
+struct KdNode<const D: usize> {
+    /// Range of `KdTree::order` covered by this node.
+    start: usize,
+    end: usize,
+    min: [i64; D],
+    max: [i64; D],
+    children: Option<(usize, usize)>,
+}
+
+/// k-d tree over box positions for nearest-neighbour queries.
+struct KdTree<'a, const D: usize> {
+    points: &'a [Point<D>],
+    metric: Metric,
+    order: Vec<usize>,
+    nodes: Vec<KdNode<D>>,
+}
+
+impl<'a, const D: usize> KdTree<'a, D> {
+    fn new(points: &'a [Point<D>], metric: Metric) -> Self {
+        let mut tree = KdTree { points, metric, order: (0..points.len()).collect(), nodes: Vec::new() };
+        if !points.is_empty() {
+            tree.build(0, points.len());
+        }
+        tree
+    }
+
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let mut min = [i64::MAX; D];
+        let mut max = [i64::MIN; D];
+        for &i in &self.order[start..end] {
+            for axis in 0..D {
+                min[axis] = min[axis].min(self.points[i].0[axis]);
+                max[axis] = max[axis].max(self.points[i].0[axis]);
+            }
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(KdNode { start, end, min, max, children: None });
+
+        // Split on the median of the widest axis
+        let widest = (0..D).max_by_key(|&a| max[a].abs_diff(min[a]));
+        if let Some(axis) = widest.filter(|_| end - start > LEAF_SIZE) {
+            let mid = (start + end) / 2;
+            let points = self.points;
+            self.order[start..end].select_nth_unstable_by_key(mid - start, |&i| points[i].0[axis]);
+
+            let left = self.build(start, mid);
+            let right = self.build(mid, end);
+            self.nodes[id].children = Some((left, right));
+        }
+
+        id
+    }
+
+    /// Smallest possible distance from `p` to anything inside `node`.
+    fn lower_bound(&self, p: &Point<D>, node: &KdNode<D>) -> Distance {
+        self.metric.combine((0..D).map(|axis| {
+            let c = p.0[axis];
+            if c < node.min[axis] {
+                node.min[axis].abs_diff(c)
+            } else if c > node.max[axis] {
+                c.abs_diff(node.max[axis])
+            } else {
+                0
+            }
+        }))
+    }
+
+    /// The `k` nearest boxes `j` to box `i` with `i < j`, sorted by `(dist, j)`.
+    fn k_nearest_after(&self, i: usize, k: usize) -> Vec<(Distance, usize)> {
+        let mut best = BinaryHeap::new();
+        if !self.nodes.is_empty() {
+            self.k_nearest_in(0, i, k, &mut best);
+        }
+        best.into_sorted_vec()
+    }
+
+    fn k_nearest_in(&self, node: usize, i: usize, k: usize, best: &mut BinaryHeap<(Distance, usize)>) {
+        let p = &self.points[i];
+        let n = &self.nodes[node];
+
+        // Equal distances may still win on index, so only prune strictly farther nodes
+        if best.len() == k && self.lower_bound(p, n) > best.peek().unwrap().0 {
+            return;
+        }
+
+        match n.children {
+            None => {
+                for &j in &self.order[n.start..n.end] {
+                    if j <= i {
+                        continue;
+                    }
+                    let candidate = (self.metric.distance(p, &self.points[j]), j);
+                    if best.len() < k {
+                        best.push(candidate);
+                    } else if candidate < *best.peek().unwrap() {
+                        best.pop();
+                        best.push(candidate);
+                    }
+                }
+            }
+            Some((left, right)) => {
+                let (near, far) = if self.lower_bound(p, &self.nodes[left]) <= self.lower_bound(p, &self.nodes[right]) {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.k_nearest_in(near, i, k, best);
+                self.k_nearest_in(far, i, k, best);
+            }
+        }
+    }
+
+    /// Component shared by every box under each node, if there is one.
+    fn node_components(&self, comp: &[usize]) -> Vec<Option<usize>> {
+        let mut node_comp = vec![None; self.nodes.len()];
+        // Children always come after their parent, so walk backwards
+        for id in (0..self.nodes.len()).rev() {
+            let n = &self.nodes[id];
+            node_comp[id] = match n.children {
+                Some((l, r)) if node_comp[l] == node_comp[r] => node_comp[l],
+                Some(_) => None,
+                None => {
+                    let first = comp[self.order[n.start]];
+                    self.order[n.start..n.end].iter().all(|&j| comp[j] == first).then_some(first)
+                }
+            };
+        }
+        node_comp
+    }
+
+    /// Improves `best` with the nearest box to `i` outside `i`'s component,
+    /// breaking distance ties by `tie_break`.
+    fn nearest_foreign(
+        &self,
+        node: usize,
+        i: usize,
+        tie_break: TieBreak,
+        comp: &[usize],
+        node_comp: &[Option<usize>],
+        best: &mut Option<Edge>,
+    ) {
+        let p = &self.points[i];
+        let n = &self.nodes[node];
+
+        if node_comp[node] == Some(comp[i]) {
+            return;
+        }
+        if best.is_some_and(|b| self.lower_bound(p, n) > b.0) {
+            return;
+        }
+
+        match n.children {
+            None => {
+                for &j in &self.order[n.start..n.end] {
+                    if comp[j] == comp[i] {
+                        continue;
+                    }
+                    let candidate = edge(self.metric.distance(p, &self.points[j]), i, j);
+                    if best.is_none_or(|b| tie_break.cmp_edges(self.points, &candidate, &b).is_lt()) {
+                        *best = Some(candidate);
+                    }
+                }
+            }
+            Some((left, right)) => {
+                let (near, far) = if self.lower_bound(p, &self.nodes[left]) <= self.lower_bound(p, &self.nodes[right]) {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.nearest_foreign(near, i, tie_break, comp, node_comp, best);
+                self.nearest_foreign(far, i, tie_break, comp, node_comp, best);
+            }
+        }
+    }
+}
+
+/// Every pair of boxes in increasing `(dist, a, b)` order, generated lazily
+/// from per-box neighbour batches instead of materialising all pairs.
+struct EdgeStream<'a, const D: usize> {
+    tree: KdTree<'a, D>,
+    /// Next candidate edge for each box that still has unseen neighbours.
+    heap: BinaryHeap<Reverse<(Edge, usize)>>,
+    /// Neighbours fetched so far for each box, and how many were asked for.
+    batches: Vec<Vec<(Distance, usize)>>,
+    requested: Vec<usize>,
+}
 
-    let positions: Vec<Pos> = parse_positions(&input);
+impl<'a, const D: usize> EdgeStream<'a, D> {
+    const FIRST_BATCH: usize = 4;
 
-    let n = positions.len();
+    fn new(points: &'a [Point<D>], metric: Metric) -> Self {
+        let tree = KdTree::new(points, metric);
+        let mut stream = EdgeStream {
+            tree,
+            heap: BinaryHeap::new(),
+            batches: vec![Vec::new(); points.len()],
+            requested: vec![0; points.len()],
+        };
+        for i in 0..points.len() {
+            stream.push_neighbour(i, 0);
+        }
+        stream
+    }
+
+    /// Queues box `i`'s `rank`-th nearest later neighbour, fetching a bigger batch if needed.
+    fn push_neighbour(&mut self, i: usize, rank: usize) {
+        if rank == self.batches[i].len() {
+            let exhausted = self.requested[i] > self.batches[i].len();
+            if exhausted && self.requested[i] > 0 {
+                return;
+            }
+            self.requested[i] = (self.requested[i] * 2).max(Self::FIRST_BATCH);
+            self.batches[i] = self.tree.k_nearest_after(i, self.requested[i]);
+        }
+
+        if let Some(&(dist, j)) = self.batches[i].get(rank) {
+            self.heap.push(Reverse(((dist, i, j), rank)));
+        }
+    }
+}
+
+impl<const D: usize> Iterator for EdgeStream<'_, D> {
+    type Item = Edge;
+
+    fn next(&mut self) -> Option<Edge> {
+        let Reverse((edge, rank)) = self.heap.pop()?;
+        self.push_neighbour(edge.1, rank + 1);
+        Some(edge)
+    }
+}
+
+/// Order in which pairs at the same distance are connected.
+///
+/// Pairs are always taken in increasing distance; the policy only decides
+/// between pairs whose distances are equal, so every policy is deterministic.
+/// It applies to circuits, [`last_connection`] and [`Dendrogram::build`] alike;
+/// the puzzle answers use [`TieBreak::ByIndex`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    /// By the pair's box indices `(a, b)` with `a < b`, i.e. input order.
+    #[default]
+    ByIndex,
+    /// By the pair's coordinates: lexicographically smaller endpoint first,
+    /// then the other endpoint, falling back to indices for duplicate points.
+    ByCoordinates,
+}
+
+impl TieBreak {
+    const ALL: [TieBreak; 2] = [TieBreak::ByIndex, TieBreak::ByCoordinates];
+
+    /// Orders edges by distance, then by this policy.
+    fn cmp_edges<const D: usize>(self, points: &[Point<D>], x: &Edge, y: &Edge) -> Ordering {
+        match self {
+            TieBreak::ByIndex => x.cmp(y),
+            TieBreak::ByCoordinates => {
+                let key = |&(dist, a, b): &Edge| {
+                    let (p, q) = (points[a].0, points[b].0);
+                    (dist, p.min(q), p.max(q), a, b)
+                };
+                key(x).cmp(&key(y))
+            }
+        }
+    }
+
+    /// Sorts edges into this policy's order.
+    fn sort<const D: usize>(self, points: &[Point<D>], edges: &mut [Edge]) {
+        edges.sort_unstable_by(|x, y| self.cmp_edges(points, x, y));
+    }
+}
+
+/// Every pair in increasing distance, ties ordered by `tie_break`.
+fn ordered_edges<'a, const D: usize>(
+    points: &'a [Point<D>],
+    metric: Metric,
+    tie_break: TieBreak,
+) -> impl Iterator<Item = Edge> + 'a {
+    let mut stream = EdgeStream::new(points, metric).peekable();
+    // Rest of the current equal-distance run, reversed so it pops in order
+    let mut run: Vec<Edge> = Vec::new();
+
+    std::iter::from_fn(move || {
+        // The stream already breaks ties by index
+        if tie_break == TieBreak::ByIndex {
+            return stream.next();
+        }
+        if run.is_empty() {
+            let first = stream.next()?;
+            run.push(first);
+            while let Some(e) = stream.next_if(|e| e.0 == first.0) {
+                run.push(e);
+            }
+            tie_break.sort(points, &mut run);
+            run.reverse();
+        }
+        run.pop()
+    })
+}
+
+/// Exact minimum spanning tree under `metric` (Borůvka over the k-d tree),
+/// with edges in the order Kruskal would connect them under `tie_break`.
+///
+/// Equal distances are ranked by `tie_break` throughout, so the tree is the
+/// one Kruskal builds with that policy, not just one of equal total length.
+fn minimum_spanning_tree<const D: usize>(points: &[Point<D>], metric: Metric, tie_break: TieBreak) -> Vec<Edge> {
+    let n = points.len();
+    let tree = KdTree::new(points, metric);
     let mut dsu = DSU::new(n);
+    let mut mst = Vec::with_capacity(n.saturating_sub(1));
 
-    let mut pairs = Vec::new();
-    for i in 0..n {
-        for j in (i + 1)..n {
-            pairs.push((positions[i].dist2(&positions[j]), i, j));
+    while dsu.components() > 1 {
+        let comp: Vec<usize> = (0..n).map(|i| dsu.find(i)).collect();
+        let node_comp = tree.node_components(&comp);
+
+        // Cheapest outgoing edge per component; the shared bound speeds up later queries
+        let mut best: Vec<Option<Edge>> = vec![None; n];
+        for i in 0..n {
+            tree.nearest_foreign(0, i, tie_break, &comp, &node_comp, &mut best[comp[i]]);
+        }
+
+        for e in best.into_iter().flatten() {
+            if dsu.union(e.1, e.2) {
+                mst.push(e);
+            }
         }
     }
 
-    pairs.sort_by_key(|p| p.0);
+    tie_break.sort(points, &mut mst);
+    mst
+}
+
+/// One step of single-linkage clustering: the circuits holding boxes `a` and
+/// `b` are joined into dendrogram node `n + step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Merge {
+    pub a: usize,
+    pub b: usize,
+    /// Distance between `a` and `b` under the dendrogram's metric, squared
+    /// for Euclidean; [`Metric::length`] turns it into the merge height.
+    pub dist: Distance,
+    /// Dendrogram nodes merged: leaves are boxes `0..n`, merge `i` creates node `n + i`.
+    pub left: usize,
+    pub right: usize,
+    /// Sizes of the two circuits before the merge.
+    pub sizes: (usize, usize),
+}
 
-    let merge_limit = if inp.is_some() { 10 } else { 1000 };
-    let mut merges = 0;
+/// Full merge history of Kruskal-style circuit building, in merge order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dendrogram {
+    boxes: usize,
+    metric: Metric,
+    merges: Vec<Merge>,
+}
 
-    for (_dist, a, b) in pairs {
-        dsu.union(a, b);
-        merges += 1;
-        if merges == merge_limit {
-            break;
+impl Dendrogram {
+    /// Merges circuits in Kruskal order, equal distances ordered by `tie_break`.
+    pub fn build<const D: usize>(points: &[Point<D>], metric: Metric, tie_break: TieBreak) -> Self {
+        let n = points.len();
+        let mut dsu = DSU::new(n);
+        // Dendrogram node currently standing for each DSU root
+        let mut node_of: Vec<usize> = (0..n).collect();
+
+        let merges = minimum_spanning_tree(points, metric, tie_break)
+            .into_iter()
+            .enumerate()
+            .map(|(step, (dist, a, b))| {
+                let (ra, rb) = (dsu.find(a), dsu.find(b));
+                let merge = Merge {
+                    a,
+                    b,
+                    dist,
+                    left: node_of[ra],
+                    right: node_of[rb],
+                    sizes: (dsu.size[ra], dsu.size[rb]),
+                };
+                dsu.union(a, b);
+                node_of[dsu.find(a)] = n + step;
+                merge
+            })
+            .collect();
+
+        Dendrogram { boxes: n, metric, merges }
+    }
+
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    fn clusters_after(&self, steps: usize) -> Vec<Vec<usize>> {
+        let mut dsu = DSU::new(self.boxes);
+        for m in &self.merges[..steps] {
+            dsu.union(m.a, m.b);
+        }
+
+        let mut by_root: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..self.boxes {
+            by_root.entry(dsu.find(i)).or_default().push(i);
+        }
+        let mut clusters: Vec<Vec<usize>> = by_root.into_values().collect();
+        clusters.sort_unstable_by_key(|c| c[0]);
+        clusters
+    }
+
+    /// Circuits formed by every merge with [`Merge::dist`] at most `max_dist`,
+    /// each sorted, ordered by their smallest box. Like `dist`, `max_dist` is
+    /// squared for Euclidean, so a cut at length 5 is `max_dist = 25`.
+    pub fn cut_at_distance(&self, max_dist: Distance) -> Vec<Vec<usize>> {
+        self.clusters_after(self.merges.partition_point(|m| m.dist <= max_dist))
+    }
+
+    /// The `clusters` circuits left just before the final `clusters - 1` merges.
+    pub fn cut_into(&self, clusters: usize) -> Vec<Vec<usize>> {
+        self.clusters_after(self.boxes.saturating_sub(clusters.max(1)))
+    }
+
+    /// Newick tree with boxes as leaf labels and merge heights turned into
+    /// branch lengths (true lengths, so Euclidean heights are square-rooted).
+    pub fn to_newick(&self) -> String {
+        enum Step {
+            Visit(usize, f64),
+            Text(&'static str),
+            Length(f64),
         }
+
+        let n = self.boxes;
+        let height = |node: usize| {
+            if node < n { 0.0 } else { self.metric.length(self.merges[node - n].dist) }
+        };
+
+        let mut out = String::new();
+        let Some(root) = (n + self.merges.len()).checked_sub(1) else {
+            return ";".to_string();
+        };
+
+        // Explicit stack: chain-shaped dendrograms are as deep as there are boxes
+        let mut stack = vec![Step::Visit(root, f64::NAN)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Text(text) => out.push_str(text),
+                Step::Length(len) => out.push_str(&format!(":{}", len)),
+                Step::Visit(node, parent_height) => {
+                    let h = height(node);
+                    if !parent_height.is_nan() {
+                        stack.push(Step::Length(parent_height - h));
+                    }
+                    if node < n {
+                        out.push_str(&node.to_string());
+                    } else {
+                        let m = &self.merges[node - n];
+                        stack.extend([
+                            Step::Text(")"),
+                            Step::Visit(m.right, h),
+                            Step::Text(","),
+                            Step::Visit(m.left, h),
+                            Step::Text("("),
+                        ]);
+                    }
+                }
+            }
+        }
+
+        out.push(';');
+        out
     }
 
-    let sizes = dsu.component_sizes();
-    sizes[0] as i64 * sizes[1] as i64 * sizes[2] as i64
+    /// JSON merge history. Each merge has both its exact `dist` (squared for
+    /// Euclidean, as in [`Merge::dist`]) and its true-length `height`, the
+    /// same value [`Dendrogram::to_newick`] uses.
+    pub fn to_json(&self) -> String {
+        let merges: Vec<String> = self
+            .merges
+            .iter()
+            .map(|m| {
+                format!(
+                    "{{\"a\":{},\"b\":{},\"dist\":{},\"height\":{},\"left\":{},\"right\":{},\"sizes\":[{},{}]}}",
+                    m.a,
+                    m.b,
+                    m.dist,
+                    self.metric.length(m.dist),
+                    m.left,
+                    m.right,
+                    m.sizes.0,
+                    m.sizes.1
+                )
+            })
+            .collect();
+        format!(
+            "{{\"boxes\":{},\"metric\":\"{}\",\"merges\":[{}]}}",
+            self.boxes,
+            self.metric.name(),
+            merges.join(",")
+        )
+    }
 }
 
-pub fn gold_star(inp: Option<&str>) -> i64 {
+/// Single-linkage dendrogram of the junction boxes.
+pub fn dendrogram(inp: Option<&str>) -> Result<Dendrogram, ParseError> {
     let input = inp.unwrap_or(include_str!("../input/day8.txt")).replace("\r\n", "\n");
+    Ok(Dendrogram::build(&parse_points::<3>(&input)?, Metric::Euclidean, TieBreak::ByIndex))
+}
 
-    let positions: Vec<Pos> = parse_positions(&input);
+/// A line that isn't `D` comma-separated integers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+}
 
-    let n = positions.len();
-    let mut dsu = DSU::new(n);
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: expected comma-separated coordinates, got {:?}", self.line, self.text)
+    }
+}
 
-    let mut pairs = Vec::new();
-    for i in 0..n {
-        for j in (i + 1)..n {
-            pairs.push((positions[i].dist2(&positions[j]), i, j));
+impl std::error::Error for ParseError {}
+
+/// Why a circuit answer couldn't be computed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CircuitError {
+    Parse(ParseError),
+    /// The last connection needs at least two boxes to join.
+    TooFewBoxes { count: usize },
+    /// The answer doesn't fit in an `i64`.
+    Overflow,
+}
+
+impl std::fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitError::Parse(e) => e.fmt(f),
+            CircuitError::TooFewBoxes { count } => write!(f, "need at least 2 junction boxes, got {}", count),
+            CircuitError::Overflow => write!(f, "answer overflows a 64-bit integer"),
         }
     }
-    pairs.sort_by_key(|p| p.0);
+}
 
-    let mut last_pair = (0, 0);
+impl std::error::Error for CircuitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CircuitError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for CircuitError {
+    fn from(e: ParseError) -> Self {
+        CircuitError::Parse(e)
+    }
+}
+
+/// Parses one `D`-dimensional point per non-blank line.
+pub fn parse_points<const D: usize>(inp: &str) -> Result<Vec<Point<D>>, ParseError> {
+    inp.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let error = || ParseError { line: i + 1, text: line.to_string() };
+            let coords: Vec<i64> = line
+                .split(',')
+                .map(|s| s.trim().parse().map_err(|_| error()))
+                .collect::<Result<_, _>>()?;
+            coords.try_into().map(Point).map_err(|_| error())
+        })
+        .collect()
+}
+
+/// Connects the `connections` closest pairs of points, then multiplies the
+/// sizes of the `largest` biggest circuits (all of them if there are fewer).
+/// Fails with [`CircuitError::Overflow`] if the product doesn't fit in an `i64`.
+pub fn circuit_product<const D: usize>(
+    points: &[Point<D>],
+    metric: Metric,
+    tie_break: TieBreak,
+    connections: usize,
+    largest: usize,
+) -> Result<i64, CircuitError> {
+    let mut dsu = DSU::new(points.len());
+
+    for (_dist, a, b) in ordered_edges(points, metric, tie_break).take(connections) {
+        dsu.union(a, b);
+    }
+
+    dsu.size_product(largest).ok_or(CircuitError::Overflow)
+}
+
+/// Equal-distance pairs straddling the connection cut-off.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TieSplit {
+    pub distance: Distance,
+    /// Pairs at `distance`, and how many of them fall inside the cut-off.
+    pub tied: usize,
+    pub taken: usize,
+    /// Distinct answers reachable by choosing which tied pairs are taken, sorted.
+    pub answers: Vec<i64>,
+    /// Whether `answers` covers every choice, or only the [`TieBreak`] policies
+    /// because there were too many choices to try.
+    pub exhaustive: bool,
+}
+
+/// A circuit product together with whether tie-breaking could change it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TieReport {
+    pub answer: i64,
+    /// Set when the last connection made ties with one left out.
+    pub split: Option<TieSplit>,
+}
+
+impl TieReport {
+    /// True if some other order of the tied pairs gives a different answer.
+    pub fn ambiguous(&self) -> bool {
+        self.split.as_ref().is_some_and(|s| s.answers.len() > 1)
+    }
+}
+
+/// Most choices of tied pairs [`tie_report`] will try one by one.
+const MAX_TIE_CHOICES: u128 = 4096;
+
+/// Like [`circuit_product`], but also reports whether the pairs at the
+/// cut-off distance tie with pairs just past it, and which answers each
+/// choice among them would give. Fails with [`CircuitError::Overflow`] if
+/// any of those answers doesn't fit in an `i64`.
+pub fn tie_report<const D: usize>(
+    points: &[Point<D>],
+    metric: Metric,
+    tie_break: TieBreak,
+    connections: usize,
+    largest: usize,
+) -> Result<TieReport, CircuitError> {
+    let answer = circuit_product(points, metric, tie_break, connections, largest)?;
+
+    let mut stream = EdgeStream::new(points, metric).peekable();
+    let inside: Vec<Edge> = stream.by_ref().take(connections).collect();
+    let Some(&(distance, _, _)) = inside.last() else {
+        return Ok(TieReport { answer, split: None });
+    };
+    let beyond: Vec<Edge> = std::iter::from_fn(|| stream.next_if(|e| e.0 == distance)).collect();
+    if beyond.is_empty() {
+        return Ok(TieReport { answer, split: None });
+    }
 
-    for (_dist, a, b) in pairs {
-        if dsu.union(a, b) {
-            last_pair = (a, b);
-            if dsu.component_sizes().len() == 1 {
+    // Everything shorter is connected whatever the order
+    let below = inside.partition_point(|e| e.0 < distance);
+    let mut base = DSU::new(points.len());
+    for &(_, a, b) in &inside[..below] {
+        base.union(a, b);
+    }
+    let tied: Vec<Edge> = inside[below..].iter().chain(&beyond).copied().collect();
+    let taken = inside.len() - below;
+
+    let product_with = |chosen: &mut dyn Iterator<Item = &Edge>| {
+        let mut dsu = base.clone();
+        for &(_, a, b) in chosen {
+            dsu.union(a, b);
+        }
+        dsu.size_product(largest).ok_or(CircuitError::Overflow)
+    };
+
+    let exhaustive = choices(tied.len(), taken) <= MAX_TIE_CHOICES;
+    let mut answers = Vec::new();
+    if exhaustive {
+        // Walk every `taken`-subset of the tied pairs in lexicographic order
+        let mut pick: Vec<usize> = (0..taken).collect();
+        loop {
+            answers.push(product_with(&mut pick.iter().map(|&i| &tied[i]))?);
+            let Some(i) = (0..taken).rev().find(|&i| pick[i] < tied.len() - taken + i) else {
                 break;
+            };
+            pick[i] += 1;
+            for j in i + 1..taken {
+                pick[j] = pick[j - 1] + 1;
             }
         }
+    } else {
+        for policy in TieBreak::ALL {
+            let mut ordered = tied.clone();
+            policy.sort(points, &mut ordered);
+            answers.push(product_with(&mut ordered[..taken].iter())?);
+        }
     }
+    answers.sort_unstable();
+    answers.dedup();
+
+    Ok(TieReport { answer, split: Some(TieSplit { distance, tied: tied.len(), taken, answers, exhaustive }) })
+}
+
+/// Binomial coefficient `n` choose `k`, saturating.
+fn choices(n: usize, k: usize) -> u128 {
+    let k = k.min(n - k) as u128;
+    (0..k).fold(1u128, |acc, i| acc.saturating_mul(n as u128 - i) / (i + 1))
+}
+
+/// The pair whose connection first joins every point into one circuit, with
+/// equal-distance pairs connected in `tie_break` order.
+pub fn last_connection<const D: usize>(
+    points: &[Point<D>],
+    metric: Metric,
+    tie_break: TieBreak,
+) -> Option<(usize, usize)> {
+    // Kruskal's last successful union is the longest edge of the minimum spanning tree
+    minimum_spanning_tree(points, metric, tie_break).pop().map(|(_dist, a, b)| (a, b))
+}
+
+pub fn silver_star(inp: Option<&str>) -> i64 {
+    silver_star_with(inp, 1000, 3).unwrap()
+}
+
+/// Connects the `connections` closest pairs of boxes, then multiplies the
+/// sizes of the `largest` biggest circuits (all of them if there are fewer).
+pub fn silver_star_with(inp: Option<&str>, connections: usize, largest: usize) -> Result<i64, CircuitError> {
+    let input = inp.unwrap_or(include_str!("../input/day8.txt")).replace("\r\n", "\n");
+
+    let positions = parse_points::<3>(&input)?;
+
+    circuit_product(&positions, Metric::Euclidean, TieBreak::ByIndex, connections, largest)
+}
+
+/// [`tie_report`] for the silver puzzle's 3D Euclidean boxes.
+pub fn silver_tie_report(
+    inp: Option<&str>,
+    connections: usize,
+    largest: usize,
+    tie_break: TieBreak,
+) -> Result<TieReport, CircuitError> {
+    let input = inp.unwrap_or(include_str!("../input/day8.txt")).replace("\r\n", "\n");
+
+    let positions = parse_points::<3>(&input)?;
+
+    tie_report(&positions, Metric::Euclidean, tie_break, connections, largest)
+}
+
+pub fn gold_star(inp: Option<&str>) -> i64 {
+    gold_star_checked(inp).unwrap()
+}
 
-    let (a, b) = last_pair;
-    positions[a].x as i64 * positions[b].x as i64
+/// Multiplies the X coordinates of the last pair of boxes to connect.
+pub fn gold_star_checked(inp: Option<&str>) -> Result<i64, CircuitError> {
+    let input = inp.unwrap_or(include_str!("../input/day8.txt")).replace("\r\n", "\n");
+
+    let positions = parse_points::<3>(&input)?;
+
+    let (a, b) = last_connection(&positions, Metric::Euclidean, TieBreak::ByIndex)
+        .ok_or(CircuitError::TooFewBoxes { count: positions.len() })?;
+
+    positions[a].0[0].checked_mul(positions[b].0[0]).ok_or(CircuitError::Overflow)
 }
 
 
@@ -170,7 +952,7 @@ mod tests {
 
     #[test]
     fn test_silver() {
-        assert_eq!(silver_star(Some(TEST_INPUT)), 40);
+        assert_eq!(silver_star_with(Some(TEST_INPUT), 10, 3), Ok(40));
         println!("{}", silver_star(None));
     }
 
@@ -180,4 +962,278 @@ mod tests {
         println!("{}", gold_star(None));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_gold_errors() {
+        assert_eq!(gold_star_checked(Some("")), Err(CircuitError::TooFewBoxes { count: 0 }));
+        assert_eq!(gold_star_checked(Some("1,2,3\n")), Err(CircuitError::TooFewBoxes { count: 1 }));
+        assert_eq!(gold_star_checked(Some("3000000000,0,0\n4000000000,0,0")), Err(CircuitError::Overflow));
+        assert_eq!(gold_star_checked(Some("-3,0,0\n4,0,0")), Ok(-12));
+    }
+
+    #[test]
+    fn test_dsu_tracking() {
+        let mut dsu = DSU::new(6);
+        assert_eq!((dsu.components(), dsu.max_size()), (6, 1));
+
+        assert!(dsu.union(0, 1));
+        assert!(dsu.union(2, 3));
+        assert!(dsu.union(1, 3));
+        assert!(!dsu.union(0, 2));
+        assert_eq!((dsu.components(), dsu.max_size()), (3, 4));
+        assert_eq!(dsu.largest_sizes(2), vec![4, 1]);
+        assert_eq!(dsu.component_sizes(), vec![4, 1, 1]);
+
+        assert!(dsu.union(4, 5));
+        assert!(dsu.union(5, 0));
+        assert_eq!((dsu.components(), dsu.max_size()), (1, 6));
+        assert_eq!(dsu.component_sizes(), vec![6]);
+    }
+
+    #[test]
+    fn test_dsu_deep_chain() {
+        // A degenerate chain that a recursive find would overflow the stack on
+        let n = 1_000_000;
+        let mut dsu = DSU::new(n);
+        dsu.parent = (1..=n).collect();
+        dsu.parent[n - 1] = n - 1;
+
+        assert_eq!(dsu.find(0), n - 1);
+        assert_eq!(dsu.parent[0], n - 1);
+        assert_eq!(dsu.parent[n / 2], n - 1);
+    }
+
+    #[test]
+    fn test_dendrogram() {
+        let tree = dendrogram(Some(TEST_INPUT)).unwrap();
+        assert_eq!(tree.merges().len(), 19);
+        assert_eq!(tree.merges()[0].sizes, (1, 1));
+        assert_eq!(tree.merges().last().unwrap().sizes.0 + tree.merges().last().unwrap().sizes.1, 20);
+        assert!(tree.merges().windows(2).all(|w| w[0].dist <= w[1].dist));
+
+        // The merge that completes the circuit is gold's pair
+        let last = tree.merges().last().unwrap();
+        let positions = parse_points::<3>(TEST_INPUT).unwrap();
+        assert_eq!(positions[last.a].0[0] * positions[last.b].0[0], 25272);
+
+        assert_eq!(tree.cut_into(1), vec![(0..20).collect::<Vec<_>>()]);
+        assert_eq!(tree.cut_into(20).len(), 20);
+        let sizes = |clusters: Vec<Vec<usize>>| {
+            let mut sizes: Vec<usize> = clusters.iter().map(|c| c.len()).collect();
+            sizes.sort_unstable_by(|a, b| b.cmp(a));
+            sizes
+        };
+        assert_eq!(sizes(tree.cut_at_distance(tree.merges()[2].dist)), sizes(tree.cut_into(17)));
+        assert_eq!(tree.cut_at_distance(0).len(), 20);
+    }
+
+    #[test]
+    fn test_dendrogram_export() {
+        let tree = dendrogram(Some("0,0,0\n3,4,0\n0,0,1\n")).unwrap();
+        let merges = tree.merges();
+        assert_eq!((merges[0].a, merges[0].b, merges[0].left, merges[0].right), (0, 2, 0, 2));
+        assert_eq!((merges[1].left, merges[1].right, merges[1].sizes), (3, 1, (2, 1)));
+
+        assert_eq!(tree.to_newick(), "((0:1,2:1):4,1:5);");
+        assert_eq!(
+            tree.to_json(),
+            "{\"boxes\":3,\"metric\":\"euclidean\",\"merges\":[\
+             {\"a\":0,\"b\":2,\"dist\":1,\"height\":1,\"left\":0,\"right\":2,\"sizes\":[1,1]},\
+             {\"a\":0,\"b\":1,\"dist\":25,\"height\":5,\"left\":3,\"right\":1,\"sizes\":[2,1]}]}"
+        );
+        assert_eq!(tree.cut_at_distance(24), vec![vec![0, 2], vec![1]]);
+        assert_eq!(tree.cut_at_distance(25), vec![vec![0, 1, 2]]);
+        assert_eq!(dendrogram(Some("")).unwrap().to_newick(), ";");
+    }
+
+    #[test]
+    fn test_connection_parameters() {
+        // After 10 connections the circuits are 5, 4, 2, 2 and seven single boxes
+        assert_eq!(silver_star_with(Some(TEST_INPUT), 10, 1), Ok(5));
+        assert_eq!(silver_star_with(Some(TEST_INPUT), 10, 4), Ok(80));
+        assert_eq!(silver_star_with(Some(TEST_INPUT), 0, 3), Ok(1));
+        assert_eq!(silver_star_with(Some(TEST_INPUT), 1000, 3), Ok(20));
+        assert_eq!(Ok(silver_star(None)), silver_star_with(None, 1000, 3));
+    }
+
+    fn random_positions<const D: usize>(n: usize, seed: u64, spread: i64) -> Vec<Point<D>> {
+        let mut rng = crate::TestRng::new(seed);
+        (0..n)
+            .map(|_| Point(std::array::from_fn(|_| rng.below(spread as u64) as i64)))
+            .collect()
+    }
+
+    fn all_pairs<const D: usize>(positions: &[Point<D>], metric: Metric) -> Vec<Edge> {
+        let mut pairs = Vec::new();
+        for i in 0..positions.len() {
+            for j in (i + 1)..positions.len() {
+                pairs.push((metric.distance(&positions[i], &positions[j]), i, j));
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn test_edge_stream_matches_brute_force() {
+        // A small spread forces plenty of equal distances
+        for (seed, spread) in [(1, 1000), (2, 10), (3, 3)] {
+            let positions = random_positions::<3>(150, seed, spread);
+            let streamed: Vec<Edge> = EdgeStream::new(&positions, Metric::Euclidean).collect();
+            assert_eq!(streamed, all_pairs(&positions, Metric::Euclidean));
+        }
+    }
+
+    #[test]
+    fn test_spanning_tree_matches_kruskal() {
+        for (seed, spread) in [(4, 1000), (5, 10), (6, 2)] {
+            let positions = random_positions::<3>(300, seed, spread);
+
+            for policy in TieBreak::ALL {
+                let mut pairs = all_pairs(&positions, Metric::Euclidean);
+                policy.sort(&positions, &mut pairs);
+                let mut dsu = DSU::new(positions.len());
+                let kruskal: Vec<Edge> = pairs.into_iter().filter(|e| dsu.union(e.1, e.2)).collect();
+
+                assert_eq!(minimum_spanning_tree(&positions, Metric::Euclidean, policy), kruskal);
+            }
+        }
+    }
+
+    fn check_metric<const D: usize>(seed: u64, spread: i64, metric: Metric) {
+        let positions = random_positions::<D>(120, seed, spread);
+        let pairs = all_pairs(&positions, metric);
+        assert_eq!(EdgeStream::new(&positions, metric).collect::<Vec<_>>(), pairs);
+
+        let mut dsu = DSU::new(positions.len());
+        let kruskal: Vec<Edge> = pairs.into_iter().filter(|e| dsu.union(e.1, e.2)).collect();
+        assert_eq!(minimum_spanning_tree(&positions, metric, TieBreak::ByIndex), kruskal);
+    }
+
+    #[test]
+    fn test_dimensions_and_metrics() {
+        for metric in [Metric::Euclidean, Metric::Manhattan, Metric::Chebyshev] {
+            check_metric::<1>(8, 50, metric);
+            check_metric::<2>(9, 20, metric);
+            check_metric::<4>(10, 6, metric);
+        }
+
+        let (a, b) = (Point([1, -2]), Point([4, 2]));
+        assert_eq!(Metric::Euclidean.distance(&a, &b), 25);
+        assert_eq!(Metric::Manhattan.distance(&a, &b), 7);
+        assert_eq!(Metric::Chebyshev.distance(&a, &b), 4);
+        assert_eq!(Metric::Euclidean.distance(&Point([i64::MIN]), &Point([i64::MAX])), (u64::MAX as u128).pow(2));
+        let (low, high) = (Point([i64::MIN / 2; 4]), Point([i64::MAX / 2; 4]));
+        assert_eq!(Metric::Euclidean.distance(&low, &high), 4 * (u64::MAX as u128 / 2).pow(2));
+        assert_eq!(Metric::Euclidean.distance(&Point([i64::MIN; 2]), &Point([i64::MAX; 2])), u128::MAX);
+
+        // Manhattan distances of 3 and 4 pair (0,0) with (3,0) before (0,4)
+        let points = parse_points::<2>("0,0\n3,0\n0,4\n").unwrap();
+        assert_eq!(last_connection(&points, Metric::Manhattan, TieBreak::ByIndex), Some((0, 2)));
+        assert_eq!(circuit_product(&points, Metric::Chebyshev, TieBreak::ByIndex, 1, 2), Ok(2));
+        assert_eq!(Dendrogram::build(&points, Metric::Manhattan, TieBreak::ByIndex).to_newick(), "((0:3,1:3):1,2:4);");
+    }
+
+    #[test]
+    fn test_tie_break() {
+        // Three pairs at distance 1: (0, 1) first by index, (2, 3) first by coordinates
+        let points = parse_points::<2>("10,0\n11,0\n0,0\n1,0\n2,0\n").unwrap();
+        assert_eq!(circuit_product(&points, Metric::Euclidean, TieBreak::ByIndex, 2, 1), Ok(2));
+        assert_eq!(circuit_product(&points, Metric::Euclidean, TieBreak::ByCoordinates, 2, 1), Ok(3));
+
+        let report = tie_report(&points, Metric::Euclidean, TieBreak::ByCoordinates, 2, 1).unwrap();
+        assert_eq!(report.answer, 3);
+        assert_eq!(
+            report.split,
+            Some(TieSplit { distance: 1, tied: 3, taken: 2, answers: vec![2, 3], exhaustive: true })
+        );
+        assert!(report.ambiguous());
+
+        // Any single tied pair gives the same circuit sizes
+        let report = tie_report(&points, Metric::Euclidean, TieBreak::ByIndex, 1, 3).unwrap();
+        assert_eq!(report.split.as_ref().map(|s| s.answers.clone()), Some(vec![2]));
+        assert!(!report.ambiguous());
+
+        // The cut-off falls between distances 1 and 64
+        assert_eq!(tie_report(&points, Metric::Euclidean, TieBreak::ByIndex, 3, 1).unwrap().split, None);
+        assert_eq!(tie_report(&points, Metric::Euclidean, TieBreak::ByIndex, 0, 1).unwrap().split, None);
+
+        assert!(!silver_tie_report(Some(TEST_INPUT), 10, 3, TieBreak::ByIndex).unwrap().ambiguous());
+        assert_eq!(silver_tie_report(Some(TEST_INPUT), 10, 3, TieBreak::ByCoordinates).unwrap().answer, 40);
+
+        // Both pairs are at distance 1, so the policy picks which one connects last
+        let points = parse_points::<1>("2\n1\n0\n").unwrap();
+        assert_eq!(last_connection(&points, Metric::Euclidean, TieBreak::ByIndex), Some((1, 2)));
+        assert_eq!(last_connection(&points, Metric::Euclidean, TieBreak::ByCoordinates), Some((0, 1)));
+        let pairs = |policy| {
+            let tree = Dendrogram::build(&points, Metric::Euclidean, policy);
+            tree.merges().iter().map(|m| (m.a, m.b)).collect::<Vec<_>>()
+        };
+        assert_eq!(pairs(TieBreak::ByIndex), vec![(0, 1), (1, 2)]);
+        assert_eq!(pairs(TieBreak::ByCoordinates), vec![(1, 2), (0, 1)]);
+    }
+
+    #[test]
+    fn test_product_overflow() {
+        // A hundred pairs of boxes multiply out to 2^100
+        let points: Vec<Point<1>> = (0..200).map(|i| Point([i / 2 * 10 + i % 2])).collect();
+        assert_eq!(circuit_product(&points, Metric::Euclidean, TieBreak::ByIndex, 100, 100), Err(CircuitError::Overflow));
+        assert_eq!(tie_report(&points, Metric::Euclidean, TieBreak::ByIndex, 100, 100), Err(CircuitError::Overflow));
+        assert_eq!(circuit_product(&points, Metric::Euclidean, TieBreak::ByIndex, 100, 62), Ok(1 << 62));
+
+        let input = points.iter().map(|p| format!("{},0,0\n", p.0[0])).collect::<String>();
+        assert_eq!(silver_star_with(Some(&input), 100, 100), Err(CircuitError::Overflow));
+        assert_eq!(CircuitError::Overflow.to_string(), "answer overflows a 64-bit integer");
+    }
+
+    #[test]
+    fn test_tie_orders_match_sorting() {
+        let positions = random_positions::<2>(80, 11, 4);
+        for policy in TieBreak::ALL {
+            let mut expected = all_pairs(&positions, Metric::Manhattan);
+            for run in expected.chunk_by_mut(|x, y| x.0 == y.0) {
+                policy.sort(&positions, run);
+            }
+            let ordered: Vec<Edge> = ordered_edges(&positions, Metric::Manhattan, policy).collect();
+            assert_eq!(ordered, expected);
+        }
+
+        // Far too many tied pairs to try every choice
+        let report = tie_report(&positions, Metric::Manhattan, TieBreak::ByIndex, 100, 3).unwrap();
+        assert!(!report.split.unwrap().exhaustive);
+        assert_eq!(choices(5, 2), 10);
+        assert_eq!(choices(4, 0), 1);
+        assert!(choices(200, 100) > MAX_TIE_CHOICES);
+    }
+
+    #[test]
+    fn test_parse_points() {
+        assert_eq!(parse_points::<2>("1, -2\n\n3,4\n"), Ok(vec![Point([1, -2]), Point([3, 4])]));
+        assert_eq!(parse_points::<2>("1,2\n1,2,3\n"), Err(ParseError { line: 2, text: "1,2,3".to_string() }));
+        assert_eq!(parse_points::<3>("1,x,3").unwrap_err().line, 1);
+
+        // Entry points report bad lines instead of panicking
+        let bad = Some("1,2,3\nbad\n");
+        let err = ParseError { line: 2, text: "bad".to_string() };
+        assert_eq!(silver_star_with(bad, 10, 3), Err(CircuitError::Parse(err.clone())));
+        assert_eq!(gold_star_checked(bad), Err(CircuitError::Parse(err.clone())));
+        assert_eq!(dendrogram(bad).unwrap_err(), err);
+        assert_eq!(silver_tie_report(bad, 10, 3, TieBreak::ByIndex).unwrap_err(), CircuitError::Parse(err.clone()));
+        assert_eq!(err.to_string(), "line 2: expected comma-separated coordinates, got \"bad\"");
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_large_cloud() {
+        use std::time::Instant;
+
+        let positions = random_positions::<3>(200_000, 7, 100_000);
+
+        let start = Instant::now();
+        let edges: Vec<Edge> = EdgeStream::new(&positions, Metric::Euclidean).take(1000).collect();
+        println!("first {} edges in {:?}", edges.len(), start.elapsed());
+
+        let start = Instant::now();
+        let mst = minimum_spanning_tree(&positions, Metric::Euclidean, TieBreak::ByIndex);
+        println!("spanning tree with {} edges in {:?}", mst.len(), start.elapsed());
+    }
+}
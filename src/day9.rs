@@ -1,7 +1,10 @@
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Pos {
-    x: i64,
-    y: i64,
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pos {
+    pub x: i64,
+    pub y: i64,
 }
 
 impl Pos {
@@ -40,27 +43,184 @@ impl Edge {
     }
 }
 
-fn parse_positions(inp: &str) -> Vec<Pos> {
-    inp.lines()
-        .map(|line| {
-            let v: Vec<i64> = line.split(',').map(|s| s.parse().unwrap()).collect();
-            Pos { x: v[0], y: v[1] }
+/// One red tile per line. Trailing blank lines are ignored; any other line
+/// that isn't `x,y` is an error, so point indices always match line numbers.
+fn parse_positions(inp: &str) -> Result<Vec<Pos>, PolygonError> {
+    inp.trim_end()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let error = || PolygonError::Parse { line: i + 1, text: line.to_string() };
+            let (x, y) = line.split_once(',').ok_or_else(error)?;
+            match (x.trim().parse(), y.trim().parse()) {
+                (Ok(x), Ok(y)) => Ok(Pos { x, y }),
+                _ => Err(error()),
+            }
         })
         .collect()
 }
 
-pub fn silver_star(inp: Option<&str>) -> i64 {
-    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
-    let positions = parse_positions(&input);
+/// Winding direction of the loop, as drawn with `y` growing downward.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+}
 
-    positions
-        .iter()
-        .enumerate()
-        .flat_map(|(i, &a)| {
-            positions.iter().skip(i + 1).map(move |&b| a.area(&b))
+/// Why the red tiles couldn't be read, or don't form a closed, simple,
+/// axis-aligned loop. Indices point into the input points; edge `i` runs
+/// from point `i` to the next.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolygonError {
+    /// 1-based input line that isn't two comma-separated integers.
+    Parse { line: usize, text: String },
+    TooFewPoints { count: usize },
+    DiagonalEdge { index: usize, from: Pos, to: Pos },
+    DuplicatePoint { first: usize, second: usize, at: Pos },
+    SelfIntersection { first: usize, second: usize, at: Pos },
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolygonError::Parse { line, text } => {
+                write!(f, "line {}: expected two comma-separated integers, got {:?}", line, text)
+            }
+            PolygonError::TooFewPoints { count } => {
+                write!(f, "a loop needs at least 4 red tiles, got {}", count)
+            }
+            PolygonError::DiagonalEdge { index, from, to } => write!(
+                f,
+                "edge from line {} ({},{}) to ({},{}) is not axis-aligned",
+                index + 1, from.x, from.y, to.x, to.y
+            ),
+            PolygonError::DuplicatePoint { first, second, at } => {
+                write!(f, "lines {} and {} are both ({},{})", first + 1, second + 1, at.x, at.y)
+            }
+            PolygonError::SelfIntersection { first, second, at } => write!(
+                f,
+                "edges from lines {} and {} meet at ({},{})",
+                first + 1, second + 1, at.x, at.y
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+/// Checks that consecutive red tiles (wrapping around) form a closed loop of
+/// horizontal and vertical edges that never touches itself, and reports
+/// which way it winds. Straight-through vertices are allowed.
+pub fn validate_loop(positions: &[Pos]) -> Result<Orientation, PolygonError> {
+    let n = positions.len();
+    if n < 4 {
+        return Err(PolygonError::TooFewPoints { count: n });
+    }
+
+    let mut seen = HashMap::new();
+    for (i, &p) in positions.iter().enumerate() {
+        if let Some(&first) = seen.get(&p) {
+            return Err(PolygonError::DuplicatePoint { first, second: i, at: p });
+        }
+        seen.insert(p, i);
+    }
+
+    let edges: Vec<Edge> = (0..n).map(|i| Edge::new(positions[i], positions[(i + 1) % n])).collect();
+    for (i, edge) in edges.iter().enumerate() {
+        if edge.min.x != edge.max.x && edge.min.y != edge.max.y {
+            return Err(PolygonError::DiagonalEdge { index: i, from: positions[i], to: positions[(i + 1) % n] });
+        }
+    }
+
+    // Sweep edges left to right; only edges overlapping in x can meet
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_unstable_by_key(|&i| edges[i].min.x);
+    let mut found: Option<(usize, usize, Pos)> = None;
+    for (k, &i) in order.iter().enumerate() {
+        for &j in &order[k + 1..] {
+            let (a, b) = (edges[i], edges[j]);
+            if b.min.x > a.max.x {
+                break;
+            }
+            if b.min.y > a.max.y || a.min.y > b.max.y {
+                continue;
+            }
+
+            // Neighbouring edges share a vertex, but must not overlap past it
+            let low = Pos { x: b.min.x.max(a.min.x), y: b.min.y.max(a.min.y) };
+            let high = Pos { x: b.max.x.min(a.max.x), y: b.max.y.min(a.max.y) };
+            let neighbours = (i + 1) % n == j || (j + 1) % n == i;
+            if neighbours && low == high {
+                continue;
+            }
+
+            let meeting = (i.min(j), i.max(j), low);
+            if found.is_none_or(|f| meeting < f) {
+                found = Some(meeting);
+            }
+        }
+    }
+    if let Some((first, second, at)) = found {
+        return Err(PolygonError::SelfIntersection { first, second, at });
+    }
+
+    // Positive shoelace sums wind clockwise when y points down
+    Ok(if twice_signed_area(positions) > 0 { Orientation::Clockwise } else { Orientation::CounterClockwise })
+}
+
+/// Shoelace formula: twice the signed area enclosed by the loop through tile centres.
+fn twice_signed_area(positions: &[Pos]) -> i128 {
+    let n = positions.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (positions[i], positions[(i + 1) % n]);
+            a.x as i128 * b.y as i128 - b.x as i128 * a.y as i128
         })
-        .max()
-        .unwrap()
+        .sum()
+}
+
+/// An axis-aligned block of tiles, usually with red tiles at two opposite corners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rectangle {
+    /// The corner tiles it was built from; red tiles in input order for the stars.
+    pub corners: (Pos, Pos),
+    /// Top-left and bottom-right tiles, inclusive.
+    pub min: Pos,
+    pub max: Pos,
+    pub area: i64,
+}
+
+impl Rectangle {
+    fn new(a: Pos, b: Pos) -> Self {
+        Rectangle {
+            corners: (a, b),
+            min: Pos { x: a.x.min(b.x), y: a.y.min(b.y) },
+            max: Pos { x: a.x.max(b.x), y: a.y.max(b.y) },
+            area: a.area(&b),
+        }
+    }
+}
+
+/// Largest rectangle with red tiles at opposite corners; the first pair wins ties.
+pub fn largest_rectangle(positions: &[Pos]) -> Option<Rectangle> {
+    let mut best: Option<Rectangle> = None;
+    for (i, &a) in positions.iter().enumerate() {
+        for &b in &positions[i + 1..] {
+            if best.is_none_or(|r| a.area(&b) > r.area) {
+                best = Some(Rectangle::new(a, b));
+            }
+        }
+    }
+    best
+}
+
+pub fn silver_rectangle(inp: Option<&str>) -> Result<Option<Rectangle>, PolygonError> {
+    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
+    Ok(largest_rectangle(&parse_positions(&input)?))
+}
+
+pub fn silver_star(inp: Option<&str>) -> i64 {
+    silver_rectangle(inp).unwrap().unwrap().area
 }
 
 fn is_inside(point: Pos, horizontal_edges: &[Edge], vertical_edges: &[Edge]) -> bool {
@@ -114,20 +274,9 @@ fn is_inside(point: Pos, horizontal_edges: &[Edge], vertical_edges: &[Edge]) ->
     crossings % 2 == 1
 }
 
-fn edges_intersect(vert: Edge, horiz: Edge) -> bool {
-    // Check if vertical edge crosses horizontal edge
-    vert.min.x > horiz.min.x
-        && vert.min.x < horiz.max.x
-        && vert.min.y < horiz.min.y
-        && vert.max.y > horiz.min.y
-}
-
-pub fn gold_star(inp: Option<&str>) -> i64 {
-    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
-    let positions = parse_positions(&input);
+/// Horizontal and vertical edges of the loop, sorted by their fixed coordinate.
+fn edge_lists(positions: &[Pos]) -> (Vec<Edge>, Vec<Edge>) {
     let n = positions.len();
-
-    // Build sorted edge lists
     let mut horizontal_edges = Vec::new();
     let mut vertical_edges = Vec::new();
 
@@ -146,66 +295,355 @@ pub fn gold_star(inp: Option<&str>) -> i64 {
     horizontal_edges.sort_unstable_by_key(|e| e.min.y);
     vertical_edges.sort_unstable_by_key(|e| e.min.x);
 
-    let mut best_area = 0;
+    (horizontal_edges, vertical_edges)
+}
+
+/// Size measures of a loop of red tiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoopMetrics {
+    /// Area enclosed by the line through the tile centres (shoelace formula).
+    pub area: i64,
+    /// Red and green tiles the loop itself passes through.
+    pub boundary_tiles: i64,
+    /// Green tiles strictly inside the loop (Pick's theorem).
+    pub interior_tiles: i64,
+}
 
-    for (i, &corner_a) in positions.iter().enumerate() {
-        for &corner_b in positions.iter().skip(i + 1) {
-            let area = corner_a.area(&corner_b);
+impl LoopMetrics {
+    pub fn total_tiles(&self) -> i64 {
+        self.boundary_tiles + self.interior_tiles
+    }
+}
 
-            if area <= best_area {
-                continue;
-            }
+/// A validated loop of red tiles, ready for point queries.
+pub struct Polygon {
+    positions: Vec<Pos>,
+    orientation: Orientation,
+    horizontal_edges: Vec<Edge>,
+    vertical_edges: Vec<Edge>,
+}
 
-            // Define rectangle bounds
-            let (left, right) = (corner_a.x.min(corner_b.x), corner_a.x.max(corner_b.x));
-            let (top, bottom) = (corner_a.y.min(corner_b.y), corner_a.y.max(corner_b.y));
+impl Polygon {
+    pub fn new(positions: Vec<Pos>) -> Result<Self, PolygonError> {
+        let orientation = validate_loop(&positions)?;
+        let (horizontal_edges, vertical_edges) = edge_lists(&positions);
+        Ok(Polygon { positions, orientation, horizontal_edges, vertical_edges })
+    }
 
-            // Check if any polygon point is strictly inside the rectangle
-            if positions.iter().any(|p| {
-                p.x > left && p.x < right && p.y > top && p.y < bottom
-            }) {
-                continue;
+    pub fn positions(&self) -> &[Pos] {
+        &self.positions
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Whether `tile` is on the loop or enclosed by it.
+    pub fn contains(&self, tile: Pos) -> bool {
+        is_inside(tile, &self.horizontal_edges, &self.vertical_edges)
+    }
+
+    pub fn metrics(&self) -> LoopMetrics {
+        let area = (twice_signed_area(&self.positions).abs() / 2) as i64;
+        // Every tile along an axis-aligned edge is a lattice point of the boundary
+        let boundary_tiles: i64 = self
+            .horizontal_edges
+            .iter()
+            .chain(&self.vertical_edges)
+            .map(|e| (e.max.x - e.min.x) + (e.max.y - e.min.y))
+            .sum();
+        // Pick: area = interior + boundary / 2 - 1
+        let interior_tiles = area - boundary_tiles / 2 + 1;
+        LoopMetrics { area, boundary_tiles, interior_tiles }
+    }
+}
+
+/// The puzzle input as a validated loop.
+pub fn polygon(inp: Option<&str>) -> Result<Polygon, PolygonError> {
+    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
+    Polygon::new(parse_positions(&input)?)
+}
+
+/// Tiles on or inside the loop, on a grid compressed to the red tiles' rows
+/// and columns plus one cell for each non-empty gap between them.
+struct CompressedGrid {
+    /// Inclusive tile span of every compressed column and row.
+    cols: Vec<(i64, i64)>,
+    rows: Vec<(i64, i64)>,
+    /// `(cols + 1) x (rows + 1)` prefix sums counting cells outside the loop.
+    outside: Vec<u32>,
+}
+
+impl CompressedGrid {
+    fn new(positions: &[Pos]) -> Self {
+        let cols = Self::spans(positions.iter().map(|p| p.x));
+        let rows = Self::spans(positions.iter().map(|p| p.y));
+        let (w, h) = (cols.len(), rows.len());
+
+        // Vertical edges as (column, first row, last row), and horizontal ones by row
+        let mut vertical = Vec::new();
+        let mut horizontal: Vec<Vec<(usize, usize)>> = vec![Vec::new(); h];
+        let n = positions.len();
+        for i in 0..n {
+            let edge = Edge::new(positions[i], positions[(i + 1) % n]);
+            let (c0, c1) = (Self::index(&cols, edge.min.x), Self::index(&cols, edge.max.x));
+            let (r0, r1) = (Self::index(&rows, edge.min.y), Self::index(&rows, edge.max.y));
+            if edge.is_horizontal() {
+                horizontal[r0].push((c0, c1));
+            } else {
+                vertical.push((c0, r0, r1));
+            }
+        }
+        vertical.sort_unstable_by_key(|&(_, r0, _)| r0);
+
+        // Fill one row at a time so only the prefix sums span the whole grid
+        let mut prefix = vec![0u32; (w + 1) * (h + 1)];
+        let mut active: Vec<(usize, usize, usize)> = Vec::new();
+        let mut next = 0;
+        let mut cover = vec![0i32; w + 1];
+        let mut flip = vec![false; w + 1];
+        for r in 0..h {
+            while next < vertical.len() && vertical[next].1 == r {
+                active.push(vertical[next]);
+                next += 1;
+            }
+            active.retain(|&(_, _, r1)| r1 >= r);
+
+            cover.fill(0);
+            flip.fill(false);
+            for &(c, _, r1) in &active {
+                cover[c] += 1;
+                cover[c + 1] -= 1;
+                // A leftward ray from the row's top tile crosses edges that continue below it
+                if r < r1 {
+                    flip[c + 1] ^= true;
+                }
+            }
+            for &(c0, c1) in &horizontal[r] {
+                cover[c0] += 1;
+                cover[c1 + 1] -= 1;
             }
 
-            // Check if all rectangle corners are inside the polygon
-            let corners = [
-                Pos { x: left, y: top },
-                Pos { x: left, y: bottom },
-                Pos { x: right, y: top },
-                Pos { x: right, y: bottom },
-            ];
+            let (mut covered, mut inside, mut row_sum) = (0, false, 0);
+            for c in 0..w {
+                covered += cover[c];
+                inside ^= flip[c];
+                row_sum += (covered == 0 && !inside) as u32;
+                prefix[(r + 1) * (w + 1) + c + 1] = prefix[r * (w + 1) + c + 1] + row_sum;
+            }
+        }
 
-            if !corners.iter().all(|&c| is_inside(c, &horizontal_edges, &vertical_edges)) {
-                continue;
+        CompressedGrid { cols, rows, outside: prefix }
+    }
+
+    /// Each distinct coordinate as its own span, with the gaps between them.
+    fn spans(coords: impl Iterator<Item = i64>) -> Vec<(i64, i64)> {
+        let mut coords: Vec<i64> = coords.collect();
+        coords.sort_unstable();
+        coords.dedup();
+
+        let mut spans = Vec::with_capacity(coords.len() * 2);
+        for (i, &c) in coords.iter().enumerate() {
+            if i > 0 && coords[i - 1] + 1 < c {
+                spans.push((coords[i - 1] + 1, c - 1));
             }
+            spans.push((c, c));
+        }
+        spans
+    }
 
-            // Check if rectangle edges cross polygon edges
-            let rect_top = Edge::new(corners[0], corners[2]);
-            let rect_bottom = Edge::new(corners[1], corners[3]);
-            let rect_left = Edge::new(corners[0], corners[1]);
-            let rect_right = Edge::new(corners[2], corners[3]);
+    fn index(spans: &[(i64, i64)], coord: i64) -> usize {
+        spans.partition_point(|&(lo, _)| lo <= coord) - 1
+    }
 
-            let has_vertical_crossing = left < right
-                && vertical_edges
-                    .iter()
-                    .take_while(|e| e.min.x < right)
-                    .any(|&v| edges_intersect(v, rect_top) || edges_intersect(v, rect_bottom));
+    /// Compressed column and row of a red tile.
+    fn cell(&self, p: Pos) -> (usize, usize) {
+        (Self::index(&self.cols, p.x), Self::index(&self.rows, p.y))
+    }
 
-            let has_horizontal_crossing = top < bottom
-                && horizontal_edges
-                    .iter()
-                    .take_while(|e| e.min.y < bottom)
-                    .any(|&h| edges_intersect(rect_left, h) || edges_intersect(rect_right, h));
+    /// Whether every tile between compressed cells `a` and `b` is on or inside the loop.
+    fn all_inside(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let w = self.cols.len() + 1;
+        let (c0, c1) = (a.0.min(b.0), a.0.max(b.0) + 1);
+        let (r0, r1) = (a.1.min(b.1), a.1.max(b.1) + 1);
+        self.outside[r1 * w + c1] + self.outside[r0 * w + c0] == self.outside[r0 * w + c1] + self.outside[r1 * w + c0]
+    }
 
-            if has_vertical_crossing || has_horizontal_crossing {
-                continue;
+    /// Largest block of tiles on or inside the loop, whatever its corners.
+    ///
+    /// Cells are all-in or all-out, so the best block spans whole cells: this is
+    /// the maximal rectangle in a histogram per row, with cell sizes as weights.
+    fn largest_block(&self) -> Option<Rectangle> {
+        let mut heights = vec![0i64; self.cols.len()];
+        let mut best: Option<Rectangle> = None;
+
+        for (r, &(_, bottom)) in self.rows.iter().enumerate() {
+            let row_height = bottom - self.rows[r].0 + 1;
+            for (c, height) in heights.iter_mut().enumerate() {
+                *height = if self.all_inside((c, r), (c, r)) { *height + row_height } else { 0 };
             }
 
-            best_area = area;
+            // Columns still open, as (first column, height), heights increasing
+            let mut stack: Vec<(usize, i64)> = Vec::new();
+            for c in 0..=self.cols.len() {
+                let height = heights.get(c).copied().unwrap_or(0);
+                let mut first = c;
+                while let Some(&(start, h)) = stack.last() {
+                    if h < height {
+                        break;
+                    }
+                    stack.pop();
+                    let (left, right) = (self.cols[start].0, self.cols[c - 1].1);
+                    let area = (right - left + 1) * h;
+                    if h > 0 && best.is_none_or(|b| area > b.area) {
+                        best = Some(Rectangle::new(Pos { x: left, y: bottom - h + 1 }, Pos { x: right, y: bottom }));
+                    }
+                    first = start;
+                }
+                stack.push((first, height));
+            }
+        }
+
+        best
+    }
+}
+
+pub fn gold_star(inp: Option<&str>) -> i64 {
+    gold_star_checked(inp).unwrap()
+}
+
+/// Largest rectangle with red tiles at opposite corners that only covers
+/// tiles on or inside the loop; the first pair wins ties.
+pub fn largest_rectangle_inside(positions: &[Pos]) -> Result<Option<Rectangle>, PolygonError> {
+    validate_loop(positions)?;
+
+    let grid = CompressedGrid::new(positions);
+    let cells: Vec<(usize, usize)> = positions.iter().map(|&p| grid.cell(p)).collect();
+
+    let mut best: Option<Rectangle> = None;
+    for i in 0..positions.len() {
+        for j in (i + 1)..positions.len() {
+            let area = positions[i].area(&positions[j]);
+            if best.is_none_or(|r| area > r.area) && grid.all_inside(cells[i], cells[j]) {
+                best = Some(Rectangle::new(positions[i], positions[j]));
+            }
         }
     }
 
-    best_area
+    Ok(best)
+}
+
+pub fn gold_rectangle(inp: Option<&str>) -> Result<Option<Rectangle>, PolygonError> {
+    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
+    largest_rectangle_inside(&parse_positions(&input)?)
+}
+
+/// Gold, after checking the red tiles really form a loop.
+pub fn gold_star_checked(inp: Option<&str>) -> Result<i64, PolygonError> {
+    Ok(gold_rectangle(inp)?.map_or(0, |r| r.area))
+}
+
+/// SVG picture of the loop, its red tiles and optionally a chosen rectangle.
+/// Each tile is a unit square centred on its coordinates.
+pub fn render_svg(positions: &[Pos], rectangle: Option<&Rectangle>) -> String {
+    let min_x = positions.iter().map(|p| p.x).min().unwrap_or(0);
+    let max_x = positions.iter().map(|p| p.x).max().unwrap_or(0);
+    let min_y = positions.iter().map(|p| p.y).min().unwrap_or(0);
+    let max_y = positions.iter().map(|p| p.y).max().unwrap_or(0);
+
+    // Keep strokes and markers visible however large the coordinates are
+    let unit = ((max_x - min_x).max(max_y - min_y) as f64 / 400.0).max(0.5);
+    let margin = 4.0 * unit;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        min_x as f64 - margin,
+        min_y as f64 - margin,
+        (max_x - min_x) as f64 + 2.0 * margin,
+        (max_y - min_y) as f64 + 2.0 * margin
+    );
+
+    let points: Vec<String> = positions.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+    svg.push_str(&format!(
+        "<polygon points=\"{}\" fill=\"#b6e3b6\" stroke=\"#2e8b57\" stroke-width=\"{}\"/>\n",
+        points.join(" "),
+        unit
+    ));
+
+    for p in positions {
+        svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"#d62728\"/>\n", p.x, p.y, unit));
+    }
+
+    if let Some(r) = rectangle {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#1f77b4\" fill-opacity=\"0.3\" stroke=\"#1f77b4\" stroke-width=\"{}\"/>\n",
+            r.min.x as f64 - 0.5,
+            r.min.y as f64 - 0.5,
+            r.max.x - r.min.x + 1,
+            r.max.y - r.min.y + 1,
+            unit
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Largest rectangle of tiles on or inside the loop, with any corners.
+pub fn largest_inscribed_rectangle(positions: &[Pos]) -> Result<Option<Rectangle>, PolygonError> {
+    validate_loop(positions)?;
+    Ok(CompressedGrid::new(positions).largest_block())
+}
+
+/// How many red-corner rectangles stay on or inside the loop, out of all pairs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RectangleCounts {
+    pub valid: u64,
+    pub total: u64,
+}
+
+pub fn count_rectangles_inside(positions: &[Pos]) -> Result<RectangleCounts, PolygonError> {
+    validate_loop(positions)?;
+
+    let grid = CompressedGrid::new(positions);
+    let cells: Vec<(usize, usize)> = positions.iter().map(|&p| grid.cell(p)).collect();
+
+    let mut valid = 0;
+    for i in 0..cells.len() {
+        valid += cells[i + 1..].iter().filter(|&&b| grid.all_inside(cells[i], b)).count() as u64;
+    }
+    let n = positions.len() as u64;
+    Ok(RectangleCounts { valid, total: n * (n - 1) / 2 })
+}
+
+pub fn inscribed_rectangle(inp: Option<&str>) -> Result<Option<Rectangle>, PolygonError> {
+    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
+    largest_inscribed_rectangle(&parse_positions(&input)?)
+}
+
+pub fn rectangle_counts(inp: Option<&str>) -> Result<RectangleCounts, PolygonError> {
+    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
+    count_rectangles_inside(&parse_positions(&input)?)
+}
+
+/// Which rectangle [`svg`] highlights.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Highlight {
+    Silver,
+    Gold,
+    Inscribed,
+}
+
+/// SVG of the input with the chosen rectangle highlighted.
+pub fn svg(inp: Option<&str>, highlight: Highlight) -> Result<String, PolygonError> {
+    let input = inp.unwrap_or(include_str!("../input/day9.txt")).replace("\r\n", "\n");
+    let positions = parse_positions(&input)?;
+    let rectangle = match highlight {
+        Highlight::Silver => largest_rectangle(&positions),
+        Highlight::Gold => largest_rectangle_inside(&positions)?,
+        Highlight::Inscribed => largest_inscribed_rectangle(&positions)?,
+    };
+    Ok(render_svg(&positions, rectangle.as_ref()))
 }
 
 #[cfg(test)]
@@ -235,4 +673,243 @@ mod tests {
         assert_eq!(gold_star(Some(TEST_INPUT)), 24);
         println!("{}", gold_star(None));
     }
-}
\ No newline at end of file
+
+    /// A histogram-shaped loop: bars of random height on a common base.
+    fn skyline(bars: usize, seed: u64) -> Vec<Pos> {
+        let mut rng = crate::TestRng::new(seed);
+        let mut next = |m: u64| rng.below(m) as i64;
+
+        let mut edges = vec![0];
+        let mut heights: Vec<i64> = Vec::new();
+        for _ in 0..bars {
+            edges.push(edges.last().unwrap() + 2 + next(20));
+            let mut h = 1 + next(40);
+            if heights.last() == Some(&h) {
+                h += 1;
+            }
+            heights.push(h);
+        }
+
+        let mut loop_ = vec![Pos { x: 0, y: 0 }, Pos { x: edges[bars], y: 0 }];
+        for i in (0..bars).rev() {
+            loop_.push(Pos { x: edges[i + 1], y: heights[i] });
+            loop_.push(Pos { x: edges[i], y: heights[i] });
+        }
+        loop_
+    }
+
+    /// Gold by testing every tile of every candidate rectangle with `is_inside`.
+    fn gold_by_tiles(positions: &[Pos]) -> i64 {
+        let (horizontal_edges, vertical_edges) = edge_lists(positions);
+        let mut best_area = 0;
+        for (i, a) in positions.iter().enumerate() {
+            for b in &positions[i + 1..] {
+                let inside = (a.x.min(b.x)..=a.x.max(b.x)).all(|x| {
+                    (a.y.min(b.y)..=a.y.max(b.y)).all(|y| is_inside(Pos { x, y }, &horizontal_edges, &vertical_edges))
+                });
+                if inside {
+                    best_area = best_area.max(a.area(b));
+                }
+            }
+        }
+        best_area
+    }
+
+    #[test]
+    fn test_gold_matches_tiles() {
+        assert_eq!(gold_by_tiles(&parse_positions(TEST_INPUT).unwrap()), 24);
+
+        for seed in 1..20 {
+            let positions = skyline(12, seed);
+            let text: String = positions.iter().map(|p| format!("{},{}\n", p.x, p.y)).collect();
+            assert_eq!(gold_star(Some(&text)), gold_by_tiles(&positions), "seed {}", seed);
+        }
+
+        let text = staircase(4);
+        assert_eq!(gold_star(Some(&text)), gold_by_tiles(&parse_positions(&text).unwrap()));
+    }
+
+    /// A loop stepping down diagonally, so every red tile has its own row and column.
+    fn staircase(steps: i64) -> String {
+        let mut text = String::new();
+        for k in 0..steps {
+            text += &format!("{},{}\n{},{}\n", 2 * k, 2 * k, 2 * k + 2, 2 * k);
+        }
+        text + &format!("{},{}\n0,{}\n", 2 * steps, 2 * steps, 2 * steps)
+    }
+
+    #[test]
+    fn test_staircase() {
+        // The best rectangle spans the left edge and the widest step
+        assert_eq!(gold_star(Some(&staircase(300))), 301 * 303);
+    }
+
+    #[test]
+    fn test_validate_loop() {
+        let loop_of = |text: &str| validate_loop(&parse_positions(text).unwrap());
+        let at = |x, y| Pos { x, y };
+
+        // The example runs clockwise on screen; reversing it flips the winding
+        assert_eq!(loop_of(TEST_INPUT), Ok(Orientation::Clockwise));
+        let reversed: String = TEST_INPUT.lines().rev().map(|l| format!("{}\n", l)).collect();
+        assert_eq!(loop_of(&reversed), Ok(Orientation::CounterClockwise));
+        assert_eq!(validate_loop(&skyline(50, 3)).map(|_| ()), Ok(()));
+        assert!(loop_of("0,0\n2,0\n4,0\n4,2\n0,2\n").is_ok());
+
+        assert_eq!(loop_of("0,0\n1,0\n1,1\n"), Err(PolygonError::TooFewPoints { count: 3 }));
+        assert_eq!(
+            loop_of("0,0\n4,0\n4,4\n1,3\n0,3\n"),
+            Err(PolygonError::DiagonalEdge { index: 2, from: at(4, 4), to: at(1, 3) })
+        );
+        assert_eq!(
+            loop_of("0,0\n4,0\n4,4\n0,4\n0,0\n"),
+            Err(PolygonError::DuplicatePoint { first: 0, second: 4, at: at(0, 0) })
+        );
+        // A bow tie: the two vertical edges cross the middle horizontal one
+        assert_eq!(
+            loop_of("0,0\n4,0\n4,4\n2,4\n2,-2\n0,-2\n"),
+            Err(PolygonError::SelfIntersection { first: 0, second: 3, at: at(2, 0) })
+        );
+        // Doubling back along the same line
+        assert_eq!(
+            loop_of("0,0\n4,0\n2,0\n2,2\n0,2\n"),
+            Err(PolygonError::SelfIntersection { first: 0, second: 1, at: at(2, 0) })
+        );
+
+        assert_eq!(gold_star_checked(Some(TEST_INPUT)), Ok(24));
+        let err = gold_star_checked(Some("0,0\n4,0\n4,4\n1,3\n0,3\n")).unwrap_err();
+        assert_eq!(err.to_string(), "edge from line 3 (4,4) to (1,3) is not axis-aligned");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let trailing = format!("{}\n\n", TEST_INPUT);
+        assert_eq!(parse_positions(&trailing), parse_positions(TEST_INPUT));
+        assert_eq!(gold_star_checked(Some(&trailing)), Ok(24));
+        assert_eq!(parse_positions(""), Ok(vec![]));
+
+        let parse_error = |line: usize, text: &str| PolygonError::Parse { line, text: text.to_string() };
+        assert_eq!(parse_positions("1,2\n3\n"), Err(parse_error(2, "3")));
+        assert_eq!(parse_positions("1,2\n\n3,4\n"), Err(parse_error(2, "")));
+        assert_eq!(parse_positions("1,2,3"), Err(parse_error(1, "1,2,3")));
+
+        // Every entry point reports the bad line instead of panicking
+        let bad = Some("7,1\n11\n11,7\n");
+        let err = parse_error(2, "11");
+        assert_eq!(silver_rectangle(bad), Err(err.clone()));
+        assert_eq!(gold_rectangle(bad), Err(err.clone()));
+        assert_eq!(gold_star_checked(bad), Err(err.clone()));
+        assert_eq!(polygon(bad).err(), Some(err.clone()));
+        assert_eq!(inscribed_rectangle(bad), Err(err.clone()));
+        assert_eq!(rectangle_counts(bad).unwrap_err(), err);
+        assert_eq!(svg(bad, Highlight::Silver), Err(err.clone()));
+        assert_eq!(err.to_string(), "line 2: expected two comma-separated integers, got \"11\"");
+    }
+
+    #[test]
+    fn test_rectangles() {
+        let at = |x, y| Pos { x, y };
+
+        let silver = silver_rectangle(Some(TEST_INPUT)).unwrap().unwrap();
+        assert_eq!(silver.corners, (at(11, 1), at(2, 5)));
+        assert_eq!((silver.min, silver.max, silver.area), (at(2, 1), at(11, 5), 50));
+
+        let gold = gold_rectangle(Some(TEST_INPUT)).unwrap().unwrap();
+        assert_eq!(gold.corners, (at(9, 5), at(2, 3)));
+        assert_eq!((gold.min, gold.max, gold.area), (at(2, 3), at(9, 5), 24));
+
+        assert_eq!(silver_rectangle(Some("")), Ok(None));
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let svg = svg(Some(TEST_INPUT), Highlight::Gold).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 -1 13 10\">"));
+        assert!(svg.contains("<polygon points=\"7,1 11,1 11,7 9,7 9,5 2,5 2,3 7,3\""));
+        assert_eq!(svg.matches("<circle").count(), 8);
+        assert!(svg.contains("<rect x=\"1.5\" y=\"2.5\" width=\"8\" height=\"3\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        assert!(!render_svg(&parse_positions(TEST_INPUT).unwrap(), None).contains("<rect"));
+    }
+
+    #[test]
+    fn test_polygon_metrics() {
+        let polygon = polygon(Some(TEST_INPUT)).unwrap();
+        assert_eq!(polygon.orientation(), Orientation::Clockwise);
+        assert_eq!(polygon.metrics(), LoopMetrics { area: 30, boundary_tiles: 30, interior_tiles: 16 });
+        assert_eq!(polygon.metrics().total_tiles(), 46);
+
+        assert!(polygon.contains(Pos { x: 7, y: 1 }));
+        assert!(polygon.contains(Pos { x: 5, y: 4 }));
+        assert!(polygon.contains(Pos { x: 10, y: 6 }));
+        assert!(!polygon.contains(Pos { x: 2, y: 2 }));
+        assert!(!polygon.contains(Pos { x: 8, y: 6 }));
+
+        // Pick's count agrees with asking every tile in the bounding box
+        for seed in 1..10 {
+            let polygon = Polygon::new(skyline(15, seed)).unwrap();
+            let (xs, ys): (Vec<i64>, Vec<i64>) = polygon.positions().iter().map(|p| (p.x, p.y)).unzip();
+            let covered = (*xs.iter().min().unwrap()..=*xs.iter().max().unwrap())
+                .flat_map(|x| (*ys.iter().min().unwrap()..=*ys.iter().max().unwrap()).map(move |y| Pos { x, y }))
+                .filter(|&p| polygon.contains(p))
+                .count();
+            assert_eq!(polygon.metrics().total_tiles(), covered as i64, "seed {}", seed);
+        }
+
+        assert!(Polygon::new(parse_positions("0,0\n4,0\n4,4\n1,3\n").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_inscribed_rectangle() {
+        let at = |x, y| Pos { x, y };
+
+        // Free corners reach the full 10x3 band; (11,3) and (11,5) aren't red
+        let best = inscribed_rectangle(Some(TEST_INPUT)).unwrap().unwrap();
+        assert_eq!((best.min, best.max, best.area), (at(2, 3), at(11, 5), 30));
+        assert_eq!(rectangle_counts(Some(TEST_INPUT)), Ok(RectangleCounts { valid: 16, total: 28 }));
+
+        // Brute force over every block of tiles in small skylines
+        for seed in 1..6 {
+            let positions = skyline(5, seed);
+            let polygon = Polygon::new(positions.clone()).unwrap();
+            let width = positions.iter().map(|p| p.x).max().unwrap() as usize + 1;
+            let height = positions.iter().map(|p| p.y).max().unwrap() as usize + 1;
+
+            let mut prefix = vec![vec![0usize; width + 1]; height + 1];
+            for y in 0..height {
+                for x in 0..width {
+                    let inside = polygon.contains(at(x as i64, y as i64)) as usize;
+                    prefix[y + 1][x + 1] = inside + prefix[y][x + 1] + prefix[y + 1][x] - prefix[y][x];
+                }
+            }
+
+            let mut best = 0;
+            for (x0, y0) in (0..width).flat_map(|x| (0..height).map(move |y| (x, y))) {
+                for (x1, y1) in (x0 + 1..=width).flat_map(|x| (y0 + 1..=height).map(move |y| (x, y))) {
+                    let area = (x1 - x0) * (y1 - y0);
+                    if prefix[y1][x1] + prefix[y0][x0] - prefix[y0][x1] - prefix[y1][x0] == area {
+                        best = best.max(area as i64);
+                    }
+                }
+            }
+
+            let inscribed = largest_inscribed_rectangle(&positions).unwrap().unwrap();
+            assert_eq!(inscribed.area, best, "seed {}", seed);
+            assert!(inscribed.area >= largest_rectangle_inside(&positions).unwrap().unwrap().area);
+        }
+    }
+
+    #[test]
+    fn test_compressed_grid() {
+        let grid = CompressedGrid::new(&parse_positions(TEST_INPUT).unwrap());
+        assert_eq!(grid.cols, vec![(2, 2), (3, 6), (7, 7), (8, 8), (9, 9), (10, 10), (11, 11)]);
+        assert_eq!(grid.rows, vec![(1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6), (7, 7)]);
+
+        let inside = |a: Pos, b: Pos| grid.all_inside(grid.cell(a), grid.cell(b));
+        assert!(inside(Pos { x: 9, y: 5 }, Pos { x: 2, y: 3 }));
+        assert!(inside(Pos { x: 11, y: 7 }, Pos { x: 9, y: 1 }));
+        assert!(!inside(Pos { x: 2, y: 5 }, Pos { x: 11, y: 1 }));
+        assert!(!inside(Pos { x: 2, y: 3 }, Pos { x: 7, y: 1 }));
+    }
+}
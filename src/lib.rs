@@ -4,11 +4,35 @@ mod day1;
 mod day2;
 mod day3;
 mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
 mod day10;
 mod day11;
-mod day12;
\ No newline at end of file
+mod day12;
+
+/// Xorshift generator for the tests' synthetic inputs.
+#[cfg(test)]
+pub(crate) struct TestRng(u64);
+
+#[cfg(test)]
+impl TestRng {
+    /// Xorshift never leaves zero, so a zero seed is bumped to one.
+    pub(crate) fn new(seed: u64) -> Self {
+        TestRng(seed.max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Value in `0..bound`; `bound` must be nonzero.
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
@@ -0,0 +1,166 @@
+use std::io::{self, BufReader};
+use std::{env, fs, process};
+
+use aoc_2025::{day5, day6, day7, day8, day9};
+use num_bigint::BigUint;
+
+fn usage() -> ! {
+    eprintln!("usage: aoc-2025 day5 explain [input]");
+    eprintln!("       aoc-2025 day5 stream [input]   (reads stdin when no input is given)");
+    eprintln!("       aoc-2025 day6 breakdown rows|ltr|rtl [input]");
+    eprintln!("       aoc-2025 day7 timelines drop|wrap|reflect [input]");
+    eprintln!("       aoc-2025 day8 circuits <connections> <largest> [input]");
+    eprintln!("       aoc-2025 day8 ties index|coords <connections> <largest> [input]");
+    eprintln!("       aoc-2025 day8 dendrogram newick|json [input]");
+    eprintln!("       aoc-2025 day9 svg silver|gold|inscribed [input]");
+    process::exit(2);
+}
+
+/// Reads the input file if one was given, otherwise the day falls back to its bundled input.
+fn read_input(path: Option<&String>) -> Option<String> {
+    path.map(|p| {
+        fs::read_to_string(p).unwrap_or_else(|e| {
+            eprintln!("failed to read {}: {}", p, e);
+            process::exit(1);
+        })
+    })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match (args.first().map(String::as_str), args.get(1).map(String::as_str)) {
+        (Some("day5"), Some("explain")) => {
+            let input = read_input(args.get(2));
+            match day5::explain(input.as_deref()) {
+                Ok(explanations) => print!("{}", day5::render_explanations(&explanations)),
+                Err(errors) => {
+                    for e in errors {
+                        eprintln!("{}", e);
+                    }
+                    process::exit(1);
+                }
+            }
+        }
+        (Some("day5"), Some("stream")) => {
+            let counts = match args.get(2) {
+                Some(path) => fs::File::open(path).and_then(|f| day5::count_streaming(BufReader::new(f))),
+                None => day5::count_streaming(io::stdin().lock()),
+            };
+            match counts {
+                Ok(counts) => println!("fresh: {}\nspoiled: {}", counts.fresh, counts.spoiled),
+                Err(e) => {
+                    eprintln!("day5 stream failed: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        (Some("day6"), Some("breakdown")) => {
+            let reading = match args.get(2).map(String::as_str) {
+                Some("rows") => day6::Reading::Rows,
+                Some("ltr") => day6::Reading::ColumnsLeftToRight,
+                Some("rtl") => day6::Reading::ColumnsRightToLeft,
+                _ => usage(),
+            };
+            let input = read_input(args.get(3));
+            let table = day6::OperatorTable::<BigUint>::default();
+            match day6::breakdown(input.as_deref(), reading, &table) {
+                Ok(solved) => print!("{}", day6::render_breakdown(&solved)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        (Some("day7"), Some("timelines")) => {
+            let boundary = match args.get(2).map(String::as_str) {
+                Some("drop") => day7::Boundary::Drop,
+                Some("wrap") => day7::Boundary::Wrap,
+                Some("reflect") => day7::Boundary::Reflect,
+                _ => usage(),
+            };
+            match day7::timeline_map(read_input(args.get(3)).as_deref(), boundary) {
+                Ok(map) => print!("{}", map),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        (Some("day8"), Some("circuits")) => {
+            let number = |i: usize| -> usize {
+                args.get(i).and_then(|a| a.parse().ok()).unwrap_or_else(|| usage())
+            };
+            let (connections, largest) = (number(2), number(3));
+            let input = read_input(args.get(4));
+            match day8::silver_star_with(input.as_deref(), connections, largest) {
+                Ok(product) => println!("{}", product),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        (Some("day8"), Some("ties")) => {
+            let tie_break = match args.get(2).map(String::as_str) {
+                Some("index") => day8::TieBreak::ByIndex,
+                Some("coords") => day8::TieBreak::ByCoordinates,
+                _ => usage(),
+            };
+            let number = |i: usize| -> usize {
+                args.get(i).and_then(|a| a.parse().ok()).unwrap_or_else(|| usage())
+            };
+            let (connections, largest) = (number(3), number(4));
+            let input = read_input(args.get(5));
+            let report = match day8::silver_tie_report(input.as_deref(), connections, largest, tie_break) {
+                Ok(report) => report,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+            println!("{}", report.answer);
+            if let Some(split) = &report.split {
+                println!(
+                    "cut-off takes {} of {} pairs at distance {}; possible answers {:?}{}",
+                    split.taken,
+                    split.tied,
+                    split.distance,
+                    split.answers,
+                    if split.exhaustive { "" } else { " (tie-break policies only)" }
+                );
+            }
+        }
+        (Some("day8"), Some("dendrogram")) => {
+            let format = args.get(2).map(String::as_str);
+            let tree = match day8::dendrogram(read_input(args.get(3)).as_deref()) {
+                Ok(tree) => tree,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+            match format {
+                Some("newick") => println!("{}", tree.to_newick()),
+                Some("json") => println!("{}", tree.to_json()),
+                _ => usage(),
+            }
+        }
+        (Some("day9"), Some("svg")) => {
+            let highlight = match args.get(2).map(String::as_str) {
+                Some("silver") => day9::Highlight::Silver,
+                Some("gold") => day9::Highlight::Gold,
+                Some("inscribed") => day9::Highlight::Inscribed,
+                _ => usage(),
+            };
+            match day9::svg(read_input(args.get(3)).as_deref(), highlight) {
+                Ok(svg) => print!("{}", svg),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        _ => usage(),
+    }
+}